@@ -0,0 +1,68 @@
+use std::path::Path;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ssq_predictor_lib::analyzer::Analyzer;
+use ssq_predictor_lib::data_manager::DataManager;
+use ssq_predictor_lib::models::{AlgorithmType, PredictionOptions, SsqRecord};
+
+/// 仓库自带的 500 期历史数据，保证每次基准测试使用相同的数据集
+fn load_fixture() -> Vec<SsqRecord> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("ssq_history.csv");
+    DataManager::import_csv(&path, b',', false).expect("无法读取基准测试数据")
+}
+
+fn bench_analyze_red_frequency(c: &mut Criterion) {
+    let records = load_fixture();
+    c.bench_function("analyze_red_frequency", |b| {
+        b.iter(|| Analyzer::analyze_red_frequency(black_box(&records), AlgorithmType::HotStaysHot))
+    });
+}
+
+fn bench_generate_predictions(c: &mut Criterion) {
+    let records = load_fixture();
+    let options = PredictionOptions::default();
+
+    let mut group = c.benchmark_group("generate_predictions");
+    // 每次调用包含 10000 次迭代，减少采样次数以控制总耗时
+    group.sample_size(10);
+    group.bench_function("hot", |b| {
+        b.iter(|| {
+            Analyzer::generate_predictions(
+                black_box(&records),
+                AlgorithmType::HotStaysHot,
+                AlgorithmType::HotStaysHot,
+                &options,
+            )
+        })
+    });
+    group.bench_function("cold", |b| {
+        b.iter(|| {
+            Analyzer::generate_predictions(
+                black_box(&records),
+                AlgorithmType::ColdBounceBack,
+                AlgorithmType::ColdBounceBack,
+                &options,
+            )
+        })
+    });
+    group.finish();
+}
+
+fn bench_merge_records(c: &mut Criterion) {
+    let records = load_fixture();
+    // 本地保留前 400 期，新获取的是后 200 期，其中 100 期重复
+    let local = records[..400].to_vec();
+    let fetched = records[300..].to_vec();
+
+    c.bench_function("merge_records", |b| {
+        b.iter(|| DataManager::merge_records(black_box(local.clone()), black_box(fetched.clone())))
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_analyze_red_frequency,
+    bench_generate_predictions,
+    bench_merge_records
+);
+criterion_main!(benches);
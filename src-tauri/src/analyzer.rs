@@ -1,23 +1,101 @@
-use std::collections::HashMap;
-use rand::Rng;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Instant;
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, Utc, Weekday};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
 
-use crate::models::{AlgorithmType, BallFrequency, PredictionResult, SsqRecord};
+use crate::models::{
+    AbsenceRun, AlgorithmProfile, AlgorithmType, AnalysisSnapshot, AnomalyKind, AnomalyReport,
+    BacktestDraw, BacktestReport, BallFrequency, BankrollReport, BlueStrategy, CombinationRank,
+    CoverageReport, DedupMode, DrawFilter, DueForecast, ExpectedMatches, FairPredictions,
+    FairnessReport, HotSixReport, IssueNumber, NumberCategory, NumberStatCard, NumberTrend,
+    OmissionStat, Parity, PlaySimulation, PredictionGrade, PredictionOptions, PredictionResult,
+    RecencyComparison, RedPair, RelationshipModel, SelectionReport, ShapePenalty, SizeSide,
+    SnapshotSections, SsqRecord, Streak, StreakReport, TemperatureWeights, WeekdayFrequency,
+    WeightInterval,
+};
 
 const RED_BALL_MIN: u8 = 1;
 const RED_BALL_MAX: u8 = 33;
 const BLUE_BALL_MIN: u8 = 1;
 const BLUE_BALL_MAX: u8 = 16;
-const PREDICTION_COUNT: usize = 10;
+pub const PREDICTION_COUNT: usize = 10;
 const ITERATION_COUNT: usize = 10000;
+/// 设置时间预算时至少完成的迭代次数，避免结果过少
+const MIN_ITERATION_COUNT: usize = 500;
+/// 每隔多少次迭代检查一次是否超时
+const DEADLINE_CHECK_INTERVAL: usize = 100;
+/// 单次迭代中不满足约束时的最大重采样次数
+const MAX_RESAMPLE_ATTEMPTS: usize = 100;
+/// 走势判断使用的近期期数
+const TREND_WINDOW: usize = 30;
+/// 红球三区划分：一区 1-11，二区 12-22，三区 23-33
+pub const RED_ZONES: [(u8, u8); 3] = [(1, 11), (12, 22), (23, 33)];
+/// 红球按十位分段：1-10、11-20、21-30，最后一段 31-33 只有 3 个号码
+pub const RED_DECADES: [(u8, u8); 4] = [(1, 10), (11, 20), (21, 30), (31, 33)];
+/// 旋转矩阵红球号码池的大小范围
+pub const WHEEL_POOL_SIZES: std::ops::RangeInclusive<usize> = 7..=12;
+/// 大号红球的下限（17-33 为大号）
+const BIG_RED_MIN: u8 = 17;
+/// "共同遗漏"号码对默认的最小当前遗漏（红球平均约 5.5 期开出一次）
+pub const CO_OVERDUE_MIN_OMISSION: usize = 10;
+/// 自由度 32 的卡方分布在 0.05 显著性水平下的临界值
+const CHI_SQUARE_CRITICAL_32: f64 = 46.194;
+/// 和值分布的分段宽度
+const SUM_BAND_WIDTH: u32 = 10;
+/// 历史标准差过小时高斯宽度的下限，避免因子过于陡峭
+const MIN_SHAPE_WIDTH: f64 = 1.0;
+/// 红球和值的理论中心（6 × 17）
+const CENTRAL_RED_SUM: f64 = 102.0;
+/// 红球和值偏离中心的最大值（183 - 102）
+const MAX_SUM_DEVIATION: f64 = 81.0;
+/// 估算购买注数时目标概率的上限，越接近 1 所需注数越趋于无穷
+const MAX_TARGET_PROBABILITY: f64 = 0.9999;
+/// 每注投注金额（元）
+pub const TICKET_PRICE: u64 = 2;
+/// 和值偏离历史均值超过多少个标准差视为异常
+const ANOMALY_SUM_SIGMAS: f64 = 4.0;
+/// 号码温度中出现率使用的时间衰减系数
+const TEMPERATURE_DECAY: f64 = 0.95;
+/// 预测"该出"至少需要的间隔个数，少于此数时可信度为 0
+const MIN_FORECAST_INTERVALS: usize = 5;
+/// 资金回测开始前至少积累的历史期数，数据太少时频率没有意义
+const BANKROLL_WARMUP_DRAWS: usize = 30;
+/// 最新一期的号码此前遗漏达到多少期算"久未出现"（红球、蓝球，约为平均间隔的 3-4 倍）
+const HIGHLIGHT_RED_DROUGHT: usize = 20;
+const HIGHLIGHT_BLUE_DROUGHT: usize = 50;
+/// 奇偶比在历史中的占比低于此值时视为少见
+const HIGHLIGHT_RARE_SHARE: f64 = 0.05;
+/// 模拟投注的重复次数
+const PLAY_SIMULATION_TRIALS: usize = 1000;
+/// 开奖所在时区相对 UTC 的小时数：中国标准时间 UTC+8，没有夏令时
+pub const DRAW_UTC_OFFSET_HOURS: i32 = 8;
+/// 双色球开奖日：每周二、四、日
+const DRAW_WEEKDAYS: [Weekday; 3] = [Weekday::Tue, Weekday::Thu, Weekday::Sun];
 
 pub struct Analyzer;
 
 impl Analyzer {
+    /// 分析实际使用的记录：与真实数据混在一起的示例数据会被排除，
+    /// 全部都是示例数据（演示模式）时原样使用
+    ///
+    /// 每个接收历史记录的分析函数开头都先调用一次；`detect_anomalies` 检查的是数据本身，不排除。
+    pub fn exclude_synthetic(records: &[SsqRecord]) -> Cow<'_, [SsqRecord]> {
+        if records.iter().all(|r| r.synthetic) || !records.iter().any(|r| r.synthetic) {
+            return Cow::Borrowed(records);
+        }
+        Cow::Owned(records.iter().filter(|r| !r.synthetic).cloned().collect())
+    }
+
     /// 分析红球频率
     pub fn analyze_red_frequency(
         records: &[SsqRecord],
         algorithm: AlgorithmType,
     ) -> Vec<BallFrequency> {
+        let records = &*Self::exclude_synthetic(records);
         let mut frequency_map: HashMap<u8, usize> = HashMap::new();
 
         // 统计每个号码出现次数
@@ -40,7 +118,7 @@ impl Analyzer {
             })
             .collect();
 
-        frequencies.sort_by(|a, b| b.frequency.cmp(&a.frequency));
+        frequencies.sort_by(|a, b| b.frequency.cmp(&a.frequency).then(a.number.cmp(&b.number)));
         frequencies
     }
 
@@ -49,6 +127,7 @@ impl Analyzer {
         records: &[SsqRecord],
         algorithm: AlgorithmType,
     ) -> Vec<BallFrequency> {
+        let records = &*Self::exclude_synthetic(records);
         let mut frequency_map: HashMap<u8, usize> = HashMap::new();
 
         // 统计每个号码出现次数
@@ -69,7 +148,7 @@ impl Analyzer {
             })
             .collect();
 
-        frequencies.sort_by(|a, b| b.frequency.cmp(&a.frequency));
+        frequencies.sort_by(|a, b| b.frequency.cmp(&a.frequency).then(a.number.cmp(&b.number)));
         println!("Blue Frequencies: {:?}", frequencies);
         frequencies
     }
@@ -81,7 +160,11 @@ impl Analyzer {
         }
 
         let base_probability = frequency as f64 / total_records as f64;
+        Self::weight_for_probability(base_probability, algorithm)
+    }
 
+    /// 根据出现概率计算权重
+    fn weight_for_probability(base_probability: f64, algorithm: AlgorithmType) -> f64 {
         match algorithm {
             // 热号恒热：频率越高，权重越大
             AlgorithmType::HotStaysHot => {
@@ -94,30 +177,271 @@ impl Analyzer {
                 let inverted = 1.0 - base_probability;
                 inverted * inverted * 100.0
             }
+            // 贴近真实：直接按出现率，不放大冷热差异
+            AlgorithmType::Realistic => base_probability * 100.0,
+        }
+    }
+
+    /// 带时间衰减的频率分析
+    ///
+    /// 最新一期的权重为 1，往前每一期乘以 `decay`（0 < decay <= 1），
+    /// 权重按衰减后的出现率计算；`frequency` 仍是原始出现次数。
+    pub fn analyze_frequency_with_decay(
+        records: &[SsqRecord],
+        algorithm: AlgorithmType,
+        is_blue: bool,
+        decay: f64,
+    ) -> Vec<BallFrequency> {
+        let records = &*Self::exclude_synthetic(records);
+        let (min, max) = Self::ball_range(is_blue);
+        let mut raw_counts: HashMap<u8, usize> = HashMap::new();
+        let mut decayed_counts: HashMap<u8, f64> = HashMap::new();
+        let mut decayed_total = 0.0;
+
+        let mut factor = 1.0;
+        for record in records.iter().rev() {
+            let balls = if is_blue {
+                vec![record.blue_ball]
+            } else {
+                record.red_balls()
+            };
+            for ball in balls {
+                *raw_counts.entry(ball).or_insert(0) += 1;
+                *decayed_counts.entry(ball).or_insert(0.0) += factor;
+            }
+            decayed_total += factor;
+            factor *= decay;
         }
+
+        let mut frequencies: Vec<BallFrequency> = (min..=max)
+            .map(|num| {
+                let probability = if decayed_total > 0.0 {
+                    decayed_counts.get(&num).unwrap_or(&0.0) / decayed_total
+                } else {
+                    0.0
+                };
+                BallFrequency {
+                    number: num,
+                    frequency: *raw_counts.get(&num).unwrap_or(&0),
+                    weight: if decayed_total > 0.0 {
+                        Self::weight_for_probability(probability, algorithm)
+                    } else {
+                        0.0
+                    },
+                }
+            })
+            .collect();
+
+        frequencies.sort_by(|a, b| b.frequency.cmp(&a.frequency).then(a.number.cmp(&b.number)));
+        frequencies
     }
 
     /// 生成预测结果
+    ///
+    /// 红球和蓝球分别按各自的算法分析频率，例如红球追热、蓝球博冷。
     pub fn generate_predictions(
         records: &[SsqRecord],
-        algorithm: AlgorithmType,
+        red_algorithm: AlgorithmType,
+        blue_algorithm: AlgorithmType,
+        options: &PredictionOptions,
     ) -> Vec<PredictionResult> {
-        let red_frequencies = Self::analyze_red_frequency(records, algorithm);
-        let blue_frequencies = Self::analyze_blue_frequency(records, algorithm);
+        Self::generate_predictions_inner(records, red_algorithm, blue_algorithm, options, None)
+    }
 
-        let mut rng = rand::thread_rng();
+    /// 生成预测并统计全部迭代中每个号码被抽中的次数（含之后被丢弃的候选），
+    /// 用于核对加权抽样的频率是否符合预期的权重
+    pub fn generate_predictions_with_histogram(
+        records: &[SsqRecord],
+        red_algorithm: AlgorithmType,
+        blue_algorithm: AlgorithmType,
+        options: &PredictionOptions,
+    ) -> SelectionReport {
+        let mut red_counts = [0usize; RED_BALL_MAX as usize + 1];
+        let mut blue_counts = [0usize; BLUE_BALL_MAX as usize + 1];
+        let predictions = Self::generate_predictions_inner(
+            records,
+            red_algorithm,
+            blue_algorithm,
+            options,
+            Some((&mut red_counts, &mut blue_counts)),
+        );
+
+        let histogram = |counts: &[usize], min: u8, max: u8| -> Vec<(u8, usize)> {
+            (min..=max).map(|n| (n, counts[n as usize])).collect()
+        };
+        SelectionReport {
+            predictions,
+            red_sampled: histogram(&red_counts, RED_BALL_MIN, RED_BALL_MAX),
+            blue_sampled: histogram(&blue_counts, BLUE_BALL_MIN, BLUE_BALL_MAX),
+        }
+    }
+
+    /// `histogram` 为 `Some` 时累计每次迭代抽中的红球和蓝球，在约束和去重之前计数
+    fn generate_predictions_inner(
+        records: &[SsqRecord],
+        red_algorithm: AlgorithmType,
+        blue_algorithm: AlgorithmType,
+        options: &PredictionOptions,
+        mut histogram: Option<(&mut [usize], &mut [usize])>,
+    ) -> Vec<PredictionResult> {
+        let records = &*Self::exclude_synthetic(records);
+        let (red_frequencies, blue_frequencies) = match options.recency_decay {
+            Some(decay) => (
+                Self::analyze_frequency_with_decay(records, red_algorithm, false, decay),
+                Self::analyze_frequency_with_decay(records, blue_algorithm, true, decay),
+            ),
+            None => (
+                Self::analyze_red_frequency(records, red_algorithm),
+                Self::analyze_blue_frequency(records, blue_algorithm),
+            ),
+        };
+
+        // 限定蓝球范围时只保留集合内的号码参与抽取
+        let blue_frequencies: Vec<BallFrequency> = match &options.blue_pool {
+            Some(pool) => blue_frequencies
+                .into_iter()
+                .filter(|f| pool.contains(&f.number))
+                .collect(),
+            None => blue_frequencies,
+        };
+
+        let (red_frequencies, blue_frequencies) = match options.belief {
+            Some(belief) => (
+                Self::blend_with_uniform(red_frequencies, belief),
+                Self::blend_with_uniform(blue_frequencies, belief),
+            ),
+            None => (red_frequencies, blue_frequencies),
+        };
+
+        // 沿用上一期蓝球时跳过蓝球的加权抽取
+        let forced_blue = if options.include_last_blue {
+            records.last().map(|record| record.blue_ball)
+        } else {
+            None
+        };
+
+        // 历史开奖组合只构建一次；数据很少时集合很小，几乎不会拦截
+        let past_combinations = if options.exclude_historical {
+            Self::historical_combinations(records)
+        } else {
+            HashSet::new()
+        };
+
+        let shape = options
+            .shape_penalty
+            .as_ref()
+            .and_then(|penalty| ShapeGaussian::resolve(records, penalty));
+
+        // 指定三区比时预先按区拆分红球频率
+        let zone_frequencies: Option<Vec<(Vec<BallFrequency>, usize)>> = options.zone_ratio.map(|ratio| {
+            RED_ZONES
+                .iter()
+                .zip(ratio)
+                .map(|(&(lo, hi), count)| {
+                    let zone: Vec<BallFrequency> = red_frequencies
+                        .iter()
+                        .filter(|f| f.number >= lo && f.number <= hi)
+                        .cloned()
+                        .collect();
+                    (zone, count as usize)
+                })
+                .collect()
+        });
+
+        // 贴近真实模式：每期开奖的（和值分段, 三区比）作为候选目标，等概率抽取即按联合分布抽样；
+        // 已指定三区比时以指定的为准
+        let realistic = match red_algorithm {
+            AlgorithmType::Realistic if options.zone_ratio.is_none() && !records.is_empty() => {
+                let targets: Vec<(u32, [u8; 3])> = records
+                    .iter()
+                    .map(|record| {
+                        let band = Self::red_sum(record) / SUM_BAND_WIDTH * SUM_BAND_WIDTH;
+                        (band, Self::zone_counts(&record.red_balls()))
+                    })
+                    .collect();
+                let red_zones: Vec<Vec<BallFrequency>> = RED_ZONES
+                    .iter()
+                    .map(|&(lo, hi)| {
+                        red_frequencies
+                            .iter()
+                            .filter(|f| f.number >= lo && f.number <= hi)
+                            .cloned()
+                            .collect()
+                    })
+                    .collect();
+                Some((targets, red_zones))
+            }
+            _ => None,
+        };
+
+        // 指定熵字符串时使用可复现的 ChaCha20，否则使用系统随机数
+        let mut rng: Box<dyn RngCore> = match &options.entropy {
+            Some(entropy) => Box::new(ChaCha20Rng::from_seed(Self::entropy_seed(entropy))),
+            None => Box::new(rand::thread_rng()),
+        };
         let mut predictions = Vec::new();
+        let deadline = options
+            .max_duration_ms
+            .map(|ms| Instant::now() + std::time::Duration::from_millis(ms));
 
         // 进行多次迭代，选出最优的组合
-        for _ in 0..ITERATION_COUNT {
-            // 基于权重随机选择红球
-            let red_balls = Self::weighted_random_selection(&red_frequencies, 6, &mut rng);
+        for iteration in 0..ITERATION_COUNT {
+            if let Some(deadline) = deadline {
+                if iteration >= MIN_ITERATION_COUNT
+                    && iteration % DEADLINE_CHECK_INTERVAL == 0
+                    && Instant::now() >= deadline
+                {
+                    break;
+                }
+            }
+
+            // 基于权重随机选择红球，不满足间距约束时重新采样；
+            // 贴近真实模式找不到符合目标的组合时退回普通加权抽取
+            let red_balls = realistic
+                .as_ref()
+                .and_then(|(targets, red_zones)| {
+                    Self::realistic_selection(targets, red_zones, options, &mut rng)
+                })
+                .or_else(|| {
+                    (0..MAX_RESAMPLE_ATTEMPTS)
+                        .map(|_| match &zone_frequencies {
+                            Some(zones) => Self::zone_weighted_selection(zones, options.temperature, &mut rng),
+                            None => Self::weighted_random_selection(&red_frequencies, 6, options.temperature, &mut rng),
+                        })
+                        .find(|reds| Self::satisfies_min_gap(reds, options.min_gap))
+                });
+            let Some(red_balls) = red_balls else {
+                continue;
+            };
             
             // 基于权重随机选择蓝球
-            let blue_ball = Self::weighted_random_selection(&blue_frequencies, 1, &mut rng)[0];
+            let blue_ball = match forced_blue {
+                Some(blue) => blue,
+                None if options.blue_from_sum => Self::blue_for_sum(&red_balls),
+                None => Self::weighted_random_selection(&blue_frequencies, 1, options.temperature, &mut rng)[0],
+            };
+
+            if let Some((red_counts, blue_counts)) = histogram.as_mut() {
+                for &ball in &red_balls {
+                    red_counts[ball as usize] += 1;
+                }
+                blue_counts[blue_ball as usize] += 1;
+            }
+
+            if !past_combinations.is_empty() && past_combinations.contains(&(red_balls.clone(), blue_ball)) {
+                continue;
+            }
 
             // 计算得分
-            let score = Self::calculate_score(&red_balls, blue_ball, &red_frequencies, &blue_frequencies);
+            let mut score = Self::calculate_score(&red_balls, blue_ball, &red_frequencies, &blue_frequencies);
+            if let Some(shape) = &shape {
+                score *= shape.factor(&red_balls);
+            }
+            if let Some(distinctness) = &options.distinctness {
+                let recent = &records[records.len().saturating_sub(distinctness.recent_draws)..];
+                let dissimilarity = Self::dissimilarity(&red_balls, blue_ball, recent);
+                score *= (1.0 - distinctness.weight) + distinctness.weight * dissimilarity;
+            }
 
             predictions.push(PredictionResult {
                 red_balls,
@@ -129,12 +453,11 @@ impl Analyzer {
         // 按得分排序
         predictions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
 
-        // 去重并返回前10个
-        let mut unique_predictions = Vec::new();
+        // 去重并返回前10个；用组合集合做 O(1) 判重，候选耗尽时直接返回不足 10 个的结果
+        let mut seen: HashSet<(Vec<u8>, Option<u8>)> = HashSet::new();
+        let mut unique_predictions = Vec::with_capacity(PREDICTION_COUNT);
         for pred in predictions {
-            if !unique_predictions.iter().any(|p: &PredictionResult| {
-                Self::is_same_prediction(p, &pred)
-            }) {
+            if seen.insert(Self::prediction_key(&pred, options.dedup_by)) {
                 unique_predictions.push(pred);
                 if unique_predictions.len() >= PREDICTION_COUNT {
                     break;
@@ -145,41 +468,118 @@ impl Analyzer {
         unique_predictions
     }
 
+    /// 贴近真实模式的红球选择：随机取一个历史目标，按其三区比在各区内加权抽取，
+    /// 直到和值落在同一分段且满足间距约束；重采样次数用完时返回 `None`
+    fn realistic_selection(
+        targets: &[(u32, [u8; 3])],
+        red_zones: &[Vec<BallFrequency>],
+        options: &PredictionOptions,
+        rng: &mut impl Rng,
+    ) -> Option<Vec<u8>> {
+        let (band, ratio) = targets[rng.gen_range(0..targets.len())];
+        let zones: Vec<(Vec<BallFrequency>, usize)> = red_zones
+            .iter()
+            .cloned()
+            .zip(ratio.iter().map(|&count| count as usize))
+            .collect();
+
+        (0..MAX_RESAMPLE_ATTEMPTS)
+            .map(|_| Self::zone_weighted_selection(&zones, options.temperature, rng))
+            .find(|reds| {
+                let sum: u32 = reds.iter().map(|&ball| ball as u32).sum();
+                sum / SUM_BAND_WIDTH * SUM_BAND_WIDTH == band && Self::satisfies_min_gap(reds, options.min_gap)
+            })
+    }
+
+    /// 把权重向平均权重收缩：`belief × 原权重 + (1 - belief) × 平均权重`，
+    /// 总权重不变，`belief` 为 0 时所有号码权重相同
+    fn blend_with_uniform(mut frequencies: Vec<BallFrequency>, belief: f64) -> Vec<BallFrequency> {
+        if frequencies.is_empty() {
+            return frequencies;
+        }
+        let average = frequencies.iter().map(|f| f.weight).sum::<f64>() / frequencies.len() as f64;
+        for frequency in &mut frequencies {
+            frequency.weight = belief * frequency.weight + (1.0 - belief) * average;
+        }
+        frequencies
+    }
+
+    /// 按三区比分别在每个区内加权抽取，合并后排序
+    fn zone_weighted_selection(
+        zones: &[(Vec<BallFrequency>, usize)],
+        temperature: Option<f64>,
+        rng: &mut impl Rng,
+    ) -> Vec<u8> {
+        let mut selected: Vec<u8> = zones
+            .iter()
+            .flat_map(|(frequencies, count)| Self::weighted_random_selection(frequencies, *count, temperature, rng))
+            .collect();
+        selected.sort();
+        selected
+    }
+
     /// 基于权重的随机选择
+    ///
+    /// 每轮按剩余号码的权重抽取一个，抽中后用 `swap_remove` 移出候选，
+    /// 保证结果互不重复且始终保持加权偏好。剩余权重全为 0 时退化为均匀抽取。
+    /// 指定 `temperature` 时先用 softmax 变换权重，温度越低越偏向高权重号码。
     fn weighted_random_selection(
         frequencies: &[BallFrequency],
         count: usize,
+        temperature: Option<f64>,
         rng: &mut impl Rng,
     ) -> Vec<u8> {
-        let total_weight: f64 = frequencies.iter().map(|f| f.weight).sum();
-        let mut selected = Vec::new();
-        let mut available: Vec<_> = frequencies.to_vec();
+        let mut available: Vec<(u8, f64)> = frequencies
+            .iter()
+            .map(|f| (f.number, f.weight.max(0.0)))
+            .collect();
+        if let Some(t) = temperature {
+            Self::apply_softmax_temperature(&mut available, t);
+        }
+
+        let mut total_weight: f64 = available.iter().map(|(_, weight)| weight).sum();
+        let mut selected = Vec::with_capacity(count);
 
         while selected.len() < count && !available.is_empty() {
-            let rand_value = rng.gen::<f64>() * total_weight;
-            let mut cumulative = 0.0;
-
-            for (i, freq) in available.iter().enumerate() {
-                cumulative += freq.weight;
-                if rand_value <= cumulative {
-                    selected.push(freq.number);
-                    available.remove(i);
-                    break;
-                }
-            }
+            let idx = if total_weight > 0.0 {
+                let mut remaining = rng.gen::<f64>() * total_weight;
+                available
+                    .iter()
+                    .position(|&(_, weight)| {
+                        if remaining < weight {
+                            true
+                        } else {
+                            remaining -= weight;
+                            false
+                        }
+                    })
+                    // 浮点累加误差可能导致落在末尾之外，取最后一个
+                    .unwrap_or(available.len() - 1)
+            } else {
+                rng.gen_range(0..available.len())
+            };
 
-            // 如果权重太低导致无法选中，随机选一个
-            if selected.len() < count && available.len() > 0 {
-                let idx = rng.gen_range(0..available.len());
-                selected.push(available[idx].number);
-                available.remove(idx);
-            }
+            let (number, weight) = available.swap_remove(idx);
+            total_weight -= weight;
+            selected.push(number);
         }
 
         selected.sort();
         selected
     }
 
+    /// 按 `exp(weight / T)` 变换权重（减去最大权重以避免溢出）
+    fn apply_softmax_temperature(weights: &mut [(u8, f64)], temperature: f64) {
+        let max_weight = weights
+            .iter()
+            .map(|&(_, weight)| weight)
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        for (_, weight) in weights.iter_mut() {
+            *weight = ((*weight - max_weight) / temperature).exp();
+        }
+    }
+
     /// 计算得分
     fn calculate_score(
         red_balls: &[u8],
@@ -204,8 +604,2984 @@ impl Analyzer {
         score
     }
 
-    /// 判断是否为相同的预测
-    fn is_same_prediction(a: &PredictionResult, b: &PredictionResult) -> bool {
-        a.red_balls == b.red_balls && a.blue_ball == b.blue_ball
+    /// 历史开奖过的组合（排序后的红球, 蓝球）
+    pub fn historical_combinations(records: &[SsqRecord]) -> HashSet<(Vec<u8>, u8)> {
+        let records = &*Self::exclude_synthetic(records);
+        records
+            .iter()
+            .map(|record| (record.sorted_red_balls(), record.blue_ball))
+            .collect()
+    }
+
+    /// 判断已排序红球的相邻间距是否都不小于 `min_gap`
+    fn satisfies_min_gap(sorted_reds: &[u8], min_gap: u8) -> bool {
+        sorted_reds.windows(2).all(|pair| pair[1] - pair[0] >= min_gap)
+    }
+
+    /// 预测的判重键（红球已排序），只按红球去重时不含蓝球
+    fn prediction_key(prediction: &PredictionResult, mode: DedupMode) -> (Vec<u8>, Option<u8>) {
+        let blue = match mode {
+            DedupMode::RedAndBlue => Some(prediction.blue_ball),
+            DedupMode::RedOnly => None,
+        };
+        (prediction.red_balls.clone(), blue)
+    }
+
+    /// 某一时刻在 UTC 偏移 `utc_offset_hours` 小时的时区中的日期，偏移超出 ±24 小时时按 UTC 计算
+    ///
+    /// 是否开奖日、数据是否过期都按设置的时区（默认北京时间）判断，
+    /// 避免本机时区不同或临近午夜时得出不一致的结论。
+    pub fn date_in_timezone(now: DateTime<Utc>, utc_offset_hours: i32) -> NaiveDate {
+        match FixedOffset::east_opt(utc_offset_hours * 3600) {
+            Some(offset) => now.with_timezone(&offset).date_naive(),
+            None => now.date_naive(),
+        }
+    }
+
+    /// 计算下一个开奖日期
+    ///
+    /// 如果 `from` 当天就是开奖日（当晚开奖），直接返回 `from`。
+    pub fn next_draw_date(from: NaiveDate) -> NaiveDate {
+        let mut date = from;
+        while !DRAW_WEEKDAYS.contains(&date.weekday()) {
+            date += Duration::days(1);
+        }
+        date
+    }
+
+    /// 统计最近 `window` 期未出现的号码（升序）
+    pub fn absent_numbers(records: &[SsqRecord], window: usize, is_blue: bool) -> Vec<u8> {
+        let records = &*Self::exclude_synthetic(records);
+        let start = records.len().saturating_sub(window);
+        let recent = &records[start..];
+
+        let (min, max) = Self::ball_range(is_blue);
+        (min..=max)
+            .filter(|&num| !recent.iter().any(|record| Self::contains_number(record, num, is_blue)))
+            .collect()
+    }
+
+    /// 统计红球两两同时出现的次数，键为 (小号, 大号)
+    pub fn red_cooccurrence(records: &[SsqRecord]) -> HashMap<RedPair, usize> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut cooccurrence: HashMap<RedPair, usize> = HashMap::new();
+
+        for record in records {
+            let reds = record.sorted_red_balls();
+            for i in 0..reds.len() {
+                for j in (i + 1)..reds.len() {
+                    *cooccurrence.entry((reds[i], reds[j])).or_insert(0) += 1;
+                }
+            }
+        }
+
+        cooccurrence
+    }
+
+    /// 计算红球两两之间的相关系数（phi 系数）
+    ///
+    /// 相比原始同现次数，phi 系数按两个号码各自的出现频率扣除了随机同现的期望值：
+    /// 正值表示比随机更常一起出现，负值表示互相"回避"。
+    /// 结果按相关系数从高到低排列，最强的正相关在前，最强的负相关在后。
+    pub fn red_correlations(records: &[SsqRecord]) -> Vec<(RedPair, f64)> {
+        let records = &*Self::exclude_synthetic(records);
+        let total = records.len() as f64;
+        let cooccurrence = Self::red_cooccurrence(records);
+
+        let mut appearances: HashMap<u8, usize> = HashMap::new();
+        for record in records {
+            for &ball in &record.red_balls() {
+                *appearances.entry(ball).or_insert(0) += 1;
+            }
+        }
+
+        let mut correlations = Vec::new();
+        for a in RED_BALL_MIN..=RED_BALL_MAX {
+            for b in (a + 1)..=RED_BALL_MAX {
+                let na = *appearances.get(&a).unwrap_or(&0) as f64;
+                let nb = *appearances.get(&b).unwrap_or(&0) as f64;
+                let nab = *cooccurrence.get(&(a, b)).unwrap_or(&0) as f64;
+
+                let denominator = (na * (total - na) * nb * (total - nb)).sqrt();
+                let phi = if denominator > 0.0 {
+                    (nab * total - na * nb) / denominator
+                } else {
+                    0.0
+                };
+
+                correlations.push(((a, b), phi));
+            }
+        }
+
+        correlations.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        correlations
+    }
+
+    /// 将预测结果排版为便于打印或抄写的投注单
+    pub fn format_ticket_slip(
+        predictions: &[PredictionResult],
+        algorithm: AlgorithmType,
+        generated_on: NaiveDate,
+    ) -> String {
+        let algorithm_name = match algorithm {
+            AlgorithmType::HotStaysHot => "热号恒热",
+            AlgorithmType::ColdBounceBack => "冷号反弹",
+            AlgorithmType::Realistic => "贴近真实",
+        };
+
+        let mut slip = String::new();
+        slip.push_str("## 双色球投注单\n");
+        slip.push_str(&format!(
+            "生成日期: {} | 算法: {}\n\n",
+            generated_on.format("%Y-%m-%d"),
+            algorithm_name
+        ));
+
+        for (idx, prediction) in predictions.iter().enumerate() {
+            let reds = prediction
+                .red_balls
+                .iter()
+                .map(|ball| format!("{:02}", ball))
+                .collect::<Vec<_>>()
+                .join(" ");
+            slip.push_str(&format!("{}. 红 {} | 蓝 {:02}\n", idx + 1, reds, prediction.blue_ball));
+        }
+
+        slip
+    }
+
+    /// 计算一注号码对某期开奖结果的中奖等级（1-6），未中奖返回 `None`
+    ///
+    /// 一等奖 6+1，二等奖 6+0，三等奖 5+1，四等奖 5+0 或 4+1，
+    /// 五等奖 4+0 或 3+1，六等奖 2+1、1+1、0+1。
+    pub fn prize_tier(red_balls: &[u8], blue_ball: u8, actual: &SsqRecord) -> Option<u8> {
+        let actual_reds = actual.red_balls();
+        let red_matches = red_balls.iter().filter(|ball| actual_reds.contains(ball)).count();
+        let blue_match = blue_ball == actual.blue_ball;
+        Self::tier_for_matches(red_matches, blue_match)
+    }
+
+    /// 按命中红球个数和蓝球是否命中确定中奖等级
+    fn tier_for_matches(red_matches: usize, blue_match: bool) -> Option<u8> {
+        match (red_matches, blue_match) {
+            (6, true) => Some(1),
+            (6, false) => Some(2),
+            (5, true) => Some(3),
+            (5, false) | (4, true) => Some(4),
+            (4, false) | (3, true) => Some(5),
+            (_, true) => Some(6),
+            _ => None,
+        }
+    }
+
+    /// 对每注预测，找出历史上能取得的最好中奖等级
+    ///
+    /// 用来说明即使是"精选"号码，放到历史开奖中也很少能中高等级奖项。
+    pub fn best_case_against_history(
+        predictions: &[PredictionResult],
+        records: &[SsqRecord],
+    ) -> Vec<(PredictionResult, Option<u8>)> {
+        let records = &*Self::exclude_synthetic(records);
+        predictions
+            .iter()
+            .map(|prediction| {
+                let best_tier = records
+                    .iter()
+                    .filter_map(|record| {
+                        Self::prize_tier(&prediction.red_balls, prediction.blue_ball, record)
+                    })
+                    .min();
+                (prediction.clone(), best_tier)
+            })
+            .collect()
+    }
+
+    /// 号码范围（红球 1-33，蓝球 1-16）
+    pub fn ball_range(is_blue: bool) -> (u8, u8) {
+        if is_blue {
+            (BLUE_BALL_MIN, BLUE_BALL_MAX)
+        } else {
+            (RED_BALL_MIN, RED_BALL_MAX)
+        }
+    }
+
+    /// 判断某期是否开出了该号码
+    fn contains_number(record: &SsqRecord, number: u8, is_blue: bool) -> bool {
+        if is_blue {
+            record.blue_ball == number
+        } else {
+            record.red_balls().contains(&number)
+        }
+    }
+
+    /// 号码出现的记录下标（按时间顺序）
+    fn appearance_indices(records: &[SsqRecord], number: u8, is_blue: bool) -> Vec<usize> {
+        records
+            .iter()
+            .enumerate()
+            .filter(|(_, record)| Self::contains_number(record, number, is_blue))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// 当前遗漏：最近一次出现之后经过的期数，从未出现时为总期数
+    pub fn current_omission(records: &[SsqRecord], number: u8, is_blue: bool) -> usize {
+        let records = &*Self::exclude_synthetic(records);
+        match records
+            .iter()
+            .rposition(|record| Self::contains_number(record, number, is_blue))
+        {
+            Some(idx) => records.len() - 1 - idx,
+            None => records.len(),
+        }
+    }
+
+    /// 最大遗漏：历史上连续未出现的最长期数（含开头和当前的遗漏）
+    pub fn max_omission(records: &[SsqRecord], number: u8, is_blue: bool) -> usize {
+        let records = &*Self::exclude_synthetic(records);
+        let mut max_gap = 0;
+        let mut gap = 0;
+        for record in records {
+            if Self::contains_number(record, number, is_blue) {
+                gap = 0;
+            } else {
+                gap += 1;
+                max_gap = max_gap.max(gap);
+            }
+        }
+        max_gap
+    }
+
+    /// 平均间隔：相邻两次出现之间相差的平均期数，出现少于两次时返回 `None`
+    pub fn average_interval(records: &[SsqRecord], number: u8, is_blue: bool) -> Option<f64> {
+        let records = &*Self::exclude_synthetic(records);
+        let indices = Self::appearance_indices(records, number, is_blue);
+        if indices.len() < 2 {
+            return None;
+        }
+
+        let total: usize = indices.windows(2).map(|pair| pair[1] - pair[0]).sum();
+        Some(total as f64 / (indices.len() - 1) as f64)
+    }
+
+    /// 走势：比较最近 `TREND_WINDOW` 期与全部历史的出现率，相差超过 20% 视为上升或下降
+    pub fn number_trend(records: &[SsqRecord], number: u8, is_blue: bool) -> NumberTrend {
+        let records = &*Self::exclude_synthetic(records);
+        if records.len() <= TREND_WINDOW {
+            return NumberTrend::Stable;
+        }
+
+        let rate = |slice: &[SsqRecord]| {
+            let hits = slice
+                .iter()
+                .filter(|record| Self::contains_number(record, number, is_blue))
+                .count();
+            hits as f64 / slice.len() as f64
+        };
+
+        let overall_rate = rate(records);
+        let recent_rate = rate(&records[records.len() - TREND_WINDOW..]);
+
+        if recent_rate > overall_rate * 1.2 {
+            NumberTrend::Rising
+        } else if recent_rate < overall_rate * 0.8 {
+            NumberTrend::Falling
+        } else {
+            NumberTrend::Stable
+        }
+    }
+
+    /// 冷热分类：出现次数比理论期望高 10% 以上为热号，低 10% 以上为冷号，其余为温号
+    pub fn number_category(records: &[SsqRecord], number: u8, is_blue: bool) -> NumberCategory {
+        let records = &*Self::exclude_synthetic(records);
+        let (min, max) = Self::ball_range(is_blue);
+        let balls_per_draw = if is_blue { 1.0 } else { 6.0 };
+        let expected = records.len() as f64 * balls_per_draw / (max - min + 1) as f64;
+        let frequency = Self::appearance_indices(records, number, is_blue).len() as f64;
+
+        if frequency > expected * 1.1 {
+            NumberCategory::Hot
+        } else if frequency < expected * 0.9 {
+            NumberCategory::Cold
+        } else {
+            NumberCategory::Warm
+        }
+    }
+
+    /// 单个号码的综合统计卡片
+    pub fn number_stat_card(records: &[SsqRecord], number: u8, is_blue: bool) -> NumberStatCard {
+        let records = &*Self::exclude_synthetic(records);
+        let indices = Self::appearance_indices(records, number, is_blue);
+        let last_seen = indices.last().map(|&idx| &records[idx]);
+
+        NumberStatCard {
+            number,
+            is_blue,
+            frequency: indices.len(),
+            current_omission: Self::current_omission(records, number, is_blue),
+            max_omission: Self::max_omission(records, number, is_blue),
+            average_interval: Self::average_interval(records, number, is_blue),
+            last_seen_issue: last_seen.map(|record| record.issue.clone()),
+            last_seen_date: last_seen.map(|record| record.date.clone()),
+            trend: Self::number_trend(records, number, is_blue),
+            category: Self::number_category(records, number, is_blue),
+        }
+    }
+
+    /// 各等级奖金（元）
+    ///
+    /// 一、二等奖为浮动奖金，这里取常见的估计值（500 万、10 万）；
+    /// 三至六等奖为固定奖金。
+    pub fn prize_amount(tier: u8) -> u64 {
+        match tier {
+            1 => 5_000_000,
+            2 => 100_000,
+            3 => 3_000,
+            4 => 200,
+            5 => 10,
+            6 => 5,
+            _ => 0,
+        }
+    }
+
+    /// 按中奖金额回测
+    ///
+    /// 对最近 `test_draws` 期逐期回测：只用该期之前的数据生成预测，
+    /// 每注按 `TICKET_PRICE` 计成本，按实际开奖的中奖等级计奖金，
+    /// 同时给出每期和累计的投资回报率。
+    pub fn backtest(
+        records: &[SsqRecord],
+        algorithm: AlgorithmType,
+        options: &PredictionOptions,
+        test_draws: usize,
+    ) -> BacktestReport {
+        let records = &*Self::exclude_synthetic(records);
+        let start = records.len().saturating_sub(test_draws).max(1);
+        Self::backtest_from(records, algorithm, options, start, PREDICTION_COUNT)
+    }
+
+    /// 从下标 `start` 的那一期回测到最新一期，每期只投注得分最高的 `tickets_per_draw` 注
+    fn backtest_from(
+        records: &[SsqRecord],
+        algorithm: AlgorithmType,
+        options: &PredictionOptions,
+        start: usize,
+        tickets_per_draw: usize,
+    ) -> BacktestReport {
+        let mut draws = Vec::new();
+        let mut total_cost = 0;
+        let mut total_winnings = 0;
+
+        for idx in start..records.len() {
+            let actual = &records[idx];
+            let mut predictions = Self::generate_predictions(&records[..idx], algorithm, algorithm, options);
+            predictions.truncate(tickets_per_draw);
+
+            let tiers: Vec<Option<u8>> = predictions
+                .iter()
+                .map(|p| Self::prize_tier(&p.red_balls, p.blue_ball, actual))
+                .collect();
+            let actual_reds = actual.red_balls();
+            let best_red_matches = predictions
+                .iter()
+                .map(|p| p.red_balls.iter().filter(|ball| actual_reds.contains(ball)).count())
+                .max()
+                .unwrap_or(0);
+
+            let cost = predictions.len() as u64 * TICKET_PRICE;
+            let winnings: u64 = tiers.iter().flatten().map(|&tier| Self::prize_amount(tier)).sum();
+            total_cost += cost;
+            total_winnings += winnings;
+
+            draws.push(BacktestDraw {
+                issue: actual.issue.clone(),
+                tickets: predictions.len(),
+                best_red_matches,
+                best_tier: tiers.iter().flatten().min().copied(),
+                cost,
+                winnings,
+                roi: Self::roi(cost, winnings),
+                cumulative_cost: total_cost,
+                cumulative_winnings: total_winnings,
+                cumulative_roi: Self::roi(total_cost, total_winnings),
+            });
+        }
+
+        BacktestReport {
+            draws,
+            total_cost,
+            total_winnings,
+            roi: Self::roi(total_cost, total_winnings),
+        }
+    }
+
+    /// 投资回报率：(奖金 - 成本) / 成本
+    fn roi(cost: u64, winnings: u64) -> f64 {
+        if cost == 0 {
+            return 0.0;
+        }
+        (winnings as f64 - cost as f64) / cost as f64
+    }
+
+    /// 红球和值
+    pub fn red_sum(record: &SsqRecord) -> u32 {
+        record.red_balls().iter().map(|&ball| ball as u32).sum()
+    }
+
+    /// 和值尾数分布：统计每期红球和值的个位数（0-9）出现次数，十个尾数都会返回
+    pub fn analyze_sum_tail_distribution(records: &[SsqRecord]) -> Vec<(u8, usize)> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut counts = [0usize; 10];
+        for record in records {
+            counts[(Self::red_sum(record) % 10) as usize] += 1;
+        }
+
+        counts
+            .iter()
+            .enumerate()
+            .map(|(tail, &count)| (tail as u8, count))
+            .collect()
+    }
+
+    /// 按条件筛选开奖记录
+    ///
+    /// 指定日期范围时，日期无法解析的记录不会被选中。
+    pub fn filter_draws(records: &[SsqRecord], filter: &DrawFilter) -> Vec<SsqRecord> {
+        let records = &*Self::exclude_synthetic(records);
+        records
+            .iter()
+            .filter(|record| {
+                if let Some(required) = &filter.required_reds {
+                    let reds = record.red_balls();
+                    if !required.iter().all(|ball| reds.contains(ball)) {
+                        return false;
+                    }
+                }
+
+                if filter.blue.is_some_and(|blue| record.blue_ball != blue) {
+                    return false;
+                }
+
+                if filter
+                    .blue_parity
+                    .is_some_and(|parity| Parity::of(record.blue_ball as u32) != parity)
+                {
+                    return false;
+                }
+
+                let sum = Self::red_sum(record);
+                if filter.sum_min.is_some_and(|min| sum < min)
+                    || filter.sum_max.is_some_and(|max| sum > max)
+                {
+                    return false;
+                }
+
+                if filter.date_from.is_some() || filter.date_to.is_some() {
+                    let Some(date) = record.get_date() else {
+                        return false;
+                    };
+                    if filter.date_from.is_some_and(|from| date < from)
+                        || filter.date_to.is_some_and(|to| date > to)
+                    {
+                        return false;
+                    }
+                }
+
+                true
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// 计算用户号码组合在指定算法下的得分排名
+    ///
+    /// 用 `calculate_score` 给组合打分，再与按同一算法加权抽取的
+    /// `ITERATION_COUNT` 个组合比较，得出百分位。
+    pub fn rank_combination(
+        records: &[SsqRecord],
+        algorithm: AlgorithmType,
+        red_balls: &[u8],
+        blue_ball: u8,
+    ) -> CombinationRank {
+        let records = &*Self::exclude_synthetic(records);
+        let red_frequencies = Self::analyze_red_frequency(records, algorithm);
+        let blue_frequencies = Self::analyze_blue_frequency(records, algorithm);
+        let score = Self::calculate_score(red_balls, blue_ball, &red_frequencies, &blue_frequencies);
+
+        let mut rng = rand::thread_rng();
+        let below = (0..ITERATION_COUNT)
+            .filter(|_| {
+                let reds = Self::weighted_random_selection(&red_frequencies, 6, None, &mut rng);
+                let blue = Self::weighted_random_selection(&blue_frequencies, 1, None, &mut rng)[0];
+                Self::calculate_score(&reds, blue, &red_frequencies, &blue_frequencies) < score
+            })
+            .count();
+
+        let percentile = below as f64 / ITERATION_COUNT as f64 * 100.0;
+        CombinationRank {
+            score,
+            percentile,
+            top_percent: 100.0 - percentile,
+            sample_size: ITERATION_COUNT,
+        }
+    }
+
+    /// 按指定策略为蓝球候选打分并排序，与红球选择完全无关
+    ///
+    /// - `Frequency`：出现次数占总期数的比例
+    /// - `Omission`：当前遗漏与平均间隔之比，越大越"该出"
+    /// - `Markov`：历史上紧跟在最新一期蓝球之后出现的概率（加一平滑）
+    pub fn recommend_blue(records: &[SsqRecord], strategy: BlueStrategy) -> Vec<(u8, f64)> {
+        let records = &*Self::exclude_synthetic(records);
+        let total = records.len().max(1) as f64;
+        let last_blue = records.last().map(|record| record.blue_ball);
+
+        let mut transitions = [0usize; BLUE_BALL_MAX as usize + 1];
+        let mut transition_total = 0;
+        if let Some(last_blue) = last_blue {
+            for pair in records.windows(2) {
+                if pair[0].blue_ball == last_blue {
+                    if let Some(count) = transitions.get_mut(pair[1].blue_ball as usize) {
+                        *count += 1;
+                        transition_total += 1;
+                    }
+                }
+            }
+        }
+
+        let mut candidates: Vec<(u8, f64)> = (BLUE_BALL_MIN..=BLUE_BALL_MAX)
+            .map(|blue| {
+                let score = match strategy {
+                    BlueStrategy::Frequency => {
+                        let hits = records.iter().filter(|r| r.blue_ball == blue).count();
+                        hits as f64 / total
+                    }
+                    BlueStrategy::Omission => {
+                        let omission = Self::current_omission(records, blue, true) as f64;
+                        match Self::average_interval(records, blue, true) {
+                            Some(interval) if interval > 0.0 => omission / interval,
+                            _ => omission / total,
+                        }
+                    }
+                    BlueStrategy::Markov => {
+                        let outcomes = (BLUE_BALL_MAX - BLUE_BALL_MIN + 1) as f64;
+                        (transitions[blue as usize] as f64 + 1.0) / (transition_total as f64 + outcomes)
+                    }
+                };
+                (blue, score)
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        candidates
+    }
+
+    /// 指定红球的同现号码：与它同期开出的其他红球按次数从多到少排列
+    ///
+    /// 该号码从未开出时返回空列表。
+    pub fn cooccurrence_for(records: &[SsqRecord], number: u8) -> Vec<(u8, usize)> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut counts = [0usize; RED_BALL_MAX as usize + 1];
+        let mut appeared = false;
+
+        for record in records {
+            let reds = record.red_balls();
+            if !reds.contains(&number) {
+                continue;
+            }
+            appeared = true;
+            for &ball in reds.iter().filter(|&&ball| ball != number) {
+                if let Some(count) = counts.get_mut(ball as usize) {
+                    *count += 1;
+                }
+            }
+        }
+
+        if !appeared {
+            return Vec::new();
+        }
+
+        let mut companions: Vec<(u8, usize)> = (RED_BALL_MIN..=RED_BALL_MAX)
+            .filter(|&ball| ball != number)
+            .map(|ball| (ball, counts[ball as usize]))
+            .collect();
+        companions.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        companions
+    }
+
+    /// 用相同的回测区间分别跑平均统计和时间衰减两种配置，比较时间衰减是否有帮助
+    pub fn compare_recency_backtest(
+        records: &[SsqRecord],
+        algorithm: AlgorithmType,
+        options: &PredictionOptions,
+        test_draws: usize,
+        decay: f64,
+    ) -> RecencyComparison {
+        let records = &*Self::exclude_synthetic(records);
+        let flat_options = PredictionOptions {
+            recency_decay: None,
+            ..options.clone()
+        };
+        let decayed_options = PredictionOptions {
+            recency_decay: Some(decay),
+            ..options.clone()
+        };
+
+        let flat = Self::backtest(records, algorithm, &flat_options, test_draws);
+        let decayed = Self::backtest(records, algorithm, &decayed_options, test_draws);
+        let decay_helps = decayed.roi > flat.roi;
+
+        RecencyComparison {
+            flat,
+            decayed,
+            decay_helps,
+        }
+    }
+
+    /// 每个号码的遗漏统计（按号码顺序）
+    pub fn omission_stats(records: &[SsqRecord], is_blue: bool) -> Vec<OmissionStat> {
+        let records = &*Self::exclude_synthetic(records);
+        let (min, max) = Self::ball_range(is_blue);
+        (min..=max)
+            .map(|number| OmissionStat {
+                number,
+                current: Self::current_omission(records, number, is_blue),
+                max: Self::max_omission(records, number, is_blue),
+                average_interval: Self::average_interval(records, number, is_blue),
+            })
+            .collect()
+    }
+
+    /// 每期红球中奇数的个数
+    pub fn odd_count(record: &SsqRecord) -> u8 {
+        record.red_balls().iter().filter(|&&ball| ball % 2 == 1).count() as u8
+    }
+
+    /// 奇偶分布：按每期奇数个数（0-6）统计期数
+    pub fn analyze_odd_even_distribution(records: &[SsqRecord]) -> Vec<(u8, usize)> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut counts = [0usize; 7];
+        for record in records {
+            counts[Self::odd_count(record) as usize] += 1;
+        }
+        counts
+            .iter()
+            .enumerate()
+            .map(|(odd, &count)| (odd as u8, count))
+            .collect()
+    }
+
+    /// 每期红球在三个区的个数
+    pub fn zone_counts(red_balls: &[u8]) -> [u8; 3] {
+        let mut counts = [0u8; 3];
+        for &ball in red_balls {
+            if let Some(zone) = RED_ZONES.iter().position(|&(lo, hi)| ball >= lo && ball <= hi) {
+                counts[zone] += 1;
+            }
+        }
+        counts
+    }
+
+    /// 三区比分布，如 "2:2:2"，按期数从多到少排列
+    pub fn analyze_zone_distribution(records: &[SsqRecord]) -> Vec<(String, usize)> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for record in records {
+            let zones = Self::zone_counts(&record.red_balls());
+            let key = format!("{}:{}:{}", zones[0], zones[1], zones[2]);
+            *counts.entry(key).or_insert(0) += 1;
+        }
+
+        let mut distribution: Vec<(String, usize)> = counts.into_iter().collect();
+        distribution.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        distribution
+    }
+
+    /// 每期红球中相邻号码（如 7-8）的对数
+    pub fn consecutive_pairs(record: &SsqRecord) -> usize {
+        record
+            .sorted_red_balls()
+            .windows(2).filter(|pair| pair[1] == pair[0] + 1).count()
+    }
+
+    /// 连号分布：按每期连号对数（0-5）统计期数
+    pub fn analyze_consecutive_distribution(records: &[SsqRecord]) -> Vec<(usize, usize)> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut counts = [0usize; 6];
+        for record in records {
+            counts[Self::consecutive_pairs(record)] += 1;
+        }
+        counts.iter().copied().enumerate().collect()
+    }
+
+    /// 每期红球中最长连号的长度，没有连号时为 1（如 7-8-9 为 3）
+    pub fn longest_consecutive_run(record: &SsqRecord) -> u8 {
+        let reds = record.sorted_red_balls();
+        let mut longest = 1;
+        let mut run = 1;
+        for pair in reds.windows(2) {
+            if pair[1] == pair[0] + 1 {
+                run += 1;
+                longest = longest.max(run);
+            } else {
+                run = 1;
+            }
+        }
+        longest
+    }
+
+    /// 最长连号长度分布：按长度 1-6 统计期数，1 表示没有连号
+    pub fn consecutive_run_length_distribution(records: &[SsqRecord]) -> Vec<(u8, usize)> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut counts = [0usize; 7];
+        for record in records {
+            counts[Self::longest_consecutive_run(record) as usize] += 1;
+        }
+        (1..=6).map(|length| (length, counts[length as usize])).collect()
+    }
+
+    /// 相邻两期重复红球个数的分布：(重复个数, 出现次数)，从 0 列到出现过的最大个数。
+    /// 每期与上一期比较，第一期没有上一期，不参与统计
+    pub fn consecutive_repeat_distribution(records: &[SsqRecord]) -> Vec<(usize, usize)> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut counts = [0usize; 7];
+        for pair in records.windows(2) {
+            let previous = pair[0].red_balls();
+            let shared = pair[1].red_balls().iter().filter(|ball| previous.contains(ball)).count();
+            counts[shared] += 1;
+        }
+
+        let max_shared = counts.iter().rposition(|&count| count > 0).unwrap_or(0);
+        (0..=max_shared).map(|shared| (shared, counts[shared])).collect()
+    }
+
+    /// 十位分段分布：按 `RED_DECADES` 的顺序统计各段红球的累计出现次数，如 ("1-10", 次数)
+    ///
+    /// 31-33 段只有 3 个号码，次数自然远少于其他段，比较时需按号码个数折算。
+    pub fn analyze_decade_distribution(records: &[SsqRecord]) -> Vec<(String, usize)> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut counts = [0usize; RED_DECADES.len()];
+        for record in records {
+            for ball in record.red_balls() {
+                if let Some(band) = RED_DECADES.iter().position(|&(lo, hi)| ball >= lo && ball <= hi) {
+                    counts[band] += 1;
+                }
+            }
+        }
+
+        RED_DECADES
+            .iter()
+            .zip(counts)
+            .map(|(&(lo, hi), count)| (format!("{}-{}", lo, hi), count))
+            .collect()
+    }
+
+    /// 和值分布：按 `SUM_BAND_WIDTH` 分段统计期数，只返回出现过的分段
+    pub fn analyze_sum_distribution(records: &[SsqRecord]) -> Vec<(u32, usize)> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut counts: HashMap<u32, usize> = HashMap::new();
+        for record in records {
+            let band = Self::red_sum(record) / SUM_BAND_WIDTH * SUM_BAND_WIDTH;
+            *counts.entry(band).or_insert(0) += 1;
+        }
+
+        let mut distribution: Vec<(u32, usize)> = counts.into_iter().collect();
+        distribution.sort();
+        distribution
+    }
+
+    /// 红球跨度（最大号 - 最小号）
+    pub fn red_span(record: &SsqRecord) -> u8 {
+        let reds = record.red_balls();
+        let max = reds.iter().max().copied().unwrap_or(0);
+        let min = reds.iter().min().copied().unwrap_or(0);
+        max - min
+    }
+
+    /// 跨度分布：只返回出现过的跨度，按跨度升序
+    pub fn analyze_span_distribution(records: &[SsqRecord]) -> Vec<(u8, usize)> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut counts: HashMap<u8, usize> = HashMap::new();
+        for record in records {
+            *counts.entry(Self::red_span(record)).or_insert(0) += 1;
+        }
+
+        let mut distribution: Vec<(u8, usize)> = counts.into_iter().collect();
+        distribution.sort();
+        distribution
+    }
+
+    /// 红球尾数分布：每个红球个位数（0-9）的出现次数
+    pub fn analyze_tail_distribution(records: &[SsqRecord]) -> Vec<(u8, usize)> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut counts = [0usize; 10];
+        for record in records {
+            for ball in record.red_balls() {
+                counts[(ball % 10) as usize] += 1;
+            }
+        }
+        counts
+            .iter()
+            .enumerate()
+            .map(|(tail, &count)| (tail as u8, count))
+            .collect()
+    }
+
+    /// 汇总各项统计的分析快照，`sections` 控制包含哪些部分
+    pub fn full_snapshot(
+        records: &[SsqRecord],
+        algorithm: AlgorithmType,
+        sections: &SnapshotSections,
+    ) -> AnalysisSnapshot {
+        let records = &*Self::exclude_synthetic(records);
+        AnalysisSnapshot {
+            record_count: records.len(),
+            first_issue: records.first().map(|r| r.issue.clone()),
+            last_issue: records.last().map(|r| r.issue.clone()),
+            algorithm,
+            red_frequency: sections
+                .frequency
+                .then(|| Self::analyze_red_frequency(records, algorithm)),
+            blue_frequency: sections
+                .frequency
+                .then(|| Self::analyze_blue_frequency(records, algorithm)),
+            red_omission: sections.omission.then(|| Self::omission_stats(records, false)),
+            blue_omission: sections.omission.then(|| Self::omission_stats(records, true)),
+            odd_even: sections
+                .odd_even
+                .then(|| Self::analyze_odd_even_distribution(records)),
+            zone: sections.zone.then(|| Self::analyze_zone_distribution(records)),
+            consecutive: sections
+                .consecutive
+                .then(|| Self::analyze_consecutive_distribution(records)),
+            sum: sections.sum.then(|| Self::analyze_sum_distribution(records)),
+            span: sections.span.then(|| Self::analyze_span_distribution(records)),
+            tail: sections.tail.then(|| Self::analyze_tail_distribution(records)),
+        }
+    }
+
+    /// 单期开奖的均衡度，取值 0-1，越大越均衡
+    ///
+    /// 三项指标的平均值：
+    /// - 奇偶：`1 - |奇数个数 - 3| / 3`
+    /// - 三区：`1 - Σ|各区个数 - 2| / 8`（全部落在一区时偏差最大为 8）
+    /// - 和值：`1 - |和值 - 102| / 81`
+    pub fn balance_score(record: &SsqRecord) -> f64 {
+        let odd = Self::odd_count(record) as f64;
+        let odd_score = 1.0 - (odd - 3.0).abs() / 3.0;
+
+        let zones = Self::zone_counts(&record.red_balls());
+        let zone_deviation: f64 = zones.iter().map(|&count| (count as f64 - 2.0).abs()).sum();
+        let zone_score = 1.0 - zone_deviation / 8.0;
+
+        let sum = Self::red_sum(record) as f64;
+        let sum_score = 1.0 - (sum - CENTRAL_RED_SUM).abs() / MAX_SUM_DEVIATION;
+
+        (odd_score + zone_score + sum_score) / 3.0
+    }
+
+    /// 最近 `window` 期按均衡度从高到低排序的 (期号, 均衡度)
+    pub fn balance_ranking(records: &[SsqRecord], window: usize) -> Vec<(String, f64)> {
+        let records = &*Self::exclude_synthetic(records);
+        let start = records.len().saturating_sub(window);
+        let mut ranking: Vec<(String, f64)> = records[start..]
+            .iter()
+            .map(|record| (record.issue.clone(), Self::balance_score(record)))
+            .collect();
+        ranking.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        ranking
+    }
+
+    /// 单注随机号码恰好中得 `tier` 等奖的理论概率，等级无效时返回 `None`
+    ///
+    /// 按命中红球个数 r 和蓝球是否命中枚举：`C(6, r) × C(27, 6 - r) × (命中 ? 1 : 15) / (C(33, 6) × 16)`。
+    pub fn tier_probability(tier: u8) -> Option<f64> {
+        if !(1..=6).contains(&tier) {
+            return None;
+        }
+
+        let red_total = binomial(33, 6) as f64;
+        let blue_total = (BLUE_BALL_MAX - BLUE_BALL_MIN + 1) as f64;
+        let ways: f64 = (0..=6u64)
+            .flat_map(|red_matches| [(red_matches, true), (red_matches, false)])
+            .filter(|&(red_matches, blue_match)| {
+                Self::tier_for_matches(red_matches as usize, blue_match) == Some(tier)
+            })
+            .map(|(red_matches, blue_match)| {
+                let red_ways = binomial(6, red_matches) * binomial(27, 6 - red_matches);
+                let blue_ways = if blue_match { 1.0 } else { blue_total - 1.0 };
+                red_ways as f64 * blue_ways
+            })
+            .sum();
+
+        Some(ways / (red_total * blue_total))
+    }
+
+    /// 要让至少一注中得 `target_tier` 等奖的概率达到 `target_prob`，需要购买的注数
+    ///
+    /// 假设每注都是相互独立的随机号码，求满足 `1 - (1 - p)^n >= target` 的最小 n。
+    /// 目标概率被限制在 (0, 0.9999] 内；等级无效时返回 0。
+    pub fn tickets_for_probability(target_tier: u8, target_prob: f64) -> u64 {
+        let Some(p) = Self::tier_probability(target_tier) else {
+            return 0;
+        };
+        let target = target_prob.clamp(f64::EPSILON, MAX_TARGET_PROBABILITY);
+
+        let tickets = ((1.0 - target).ln() / (1.0 - p).ln()).ceil();
+        tickets.max(1.0) as u64
+    }
+
+    /// 红球中大号与小号哪边多
+    pub fn size_side(record: &SsqRecord) -> SizeSide {
+        let big = record.red_balls().iter().filter(|&&ball| ball >= BIG_RED_MIN).count();
+        match big {
+            0..=2 => SizeSide::Small,
+            3 => SizeSide::Balanced,
+            _ => SizeSide::Big,
+        }
+    }
+
+    /// 当前连续走势及各自的历史最长连续期数
+    ///
+    /// 从最新一期往前数，直到属性变化为止。少于两期时无法形成连续，全部返回 0。
+    pub fn current_streaks(records: &[SsqRecord]) -> StreakReport {
+        let records = &*Self::exclude_synthetic(records);
+        StreakReport {
+            sum_parity: Self::streak(records, |r| Parity::of(Self::red_sum(r))),
+            size_side: Self::streak(records, Self::size_side),
+            blue_parity: Self::streak(records, |r| Parity::of(r.blue_ball as u32)),
+        }
+    }
+
+    fn streak<T: PartialEq>(records: &[SsqRecord], property: impl Fn(&SsqRecord) -> T) -> Streak<T> {
+        if records.len() < 2 {
+            return Streak { value: None, current: 0, max: 0 };
+        }
+
+        let values: Vec<T> = records.iter().map(property).collect();
+        let mut max = 0;
+        let mut run = 0;
+        for i in 0..values.len() {
+            run = if i > 0 && values[i] == values[i - 1] { run + 1 } else { 1 };
+            max = max.max(run);
+        }
+
+        Streak {
+            value: values.into_iter().last(),
+            current: run,
+            max,
+        }
+    }
+
+    /// 6 个红球平均命中的个数（理论值）
+    ///
+    /// 命中个数服从超几何分布：从 33 个号码中开出 6 个，选中的 6 个里命中的个数期望为
+    /// `6 × 6 / 33`。每个号码被开出的概率都是 6/33，按期望的线性性相加即可，
+    /// 与选了哪些号码无关。
+    pub fn expected_red_matches(red_balls: &[u8]) -> f64 {
+        let drawn = 6.0;
+        let pool = (RED_BALL_MAX - RED_BALL_MIN + 1) as f64;
+        red_balls.len() as f64 * drawn / pool
+    }
+
+    /// 理论期望与按历史出现率计算的期望（每个号码的概率取其历史出现期数 / 总期数）
+    pub fn expected_matches(records: &[SsqRecord], red_balls: &[u8]) -> ExpectedMatches {
+        let records = &*Self::exclude_synthetic(records);
+        let empirical = if records.is_empty() {
+            0.0
+        } else {
+            let total = records.len() as f64;
+            red_balls
+                .iter()
+                .map(|&ball| records.iter().filter(|r| r.red_balls().contains(&ball)).count() as f64 / total)
+                .sum()
+        };
+
+        ExpectedMatches {
+            theoretical: Self::expected_red_matches(red_balls),
+            empirical,
+        }
+    }
+
+    /// 回测"热号六码"策略
+    ///
+    /// 对每一期（前面至少有 `window` 期数据），取其前 `window` 期出现次数最多的 6 个红球
+    /// 和出现次数最多的蓝球作为投注号码，记录与实际开奖对比的中奖等级。
+    pub fn hot_six_backtest(records: &[SsqRecord], window: usize) -> HotSixReport {
+        let records = &*Self::exclude_synthetic(records);
+        let mut draws = Vec::new();
+
+        for idx in window..records.len() {
+            let history = &records[idx - window..idx];
+            let mut reds: Vec<u8> = Self::analyze_red_frequency(history, AlgorithmType::HotStaysHot)
+                .iter()
+                .take(6)
+                .map(|f| f.number)
+                .collect();
+            reds.sort();
+            let blue = Self::analyze_blue_frequency(history, AlgorithmType::HotStaysHot)[0].number;
+
+            let actual = &records[idx];
+            draws.push((actual.issue.clone(), Self::prize_tier(&reds, blue, actual)));
+        }
+
+        let hits = draws.iter().filter(|(_, tier)| tier.is_some()).count();
+        let hit_rate = if draws.is_empty() {
+            0.0
+        } else {
+            hits as f64 / draws.len() as f64
+        };
+
+        HotSixReport { draws, hits, hit_rate }
+    }
+
+    /// 在上一注的基础上小幅调整号码
+    ///
+    /// 去掉上一注中权重最低的 `change_count` 个红球，每个都从未选中的红球里按权重抽取替换，
+    /// 只接受权重不低于原号码的替换，没有这样的候选时保留原号码，因此得分不会下降。
+    /// 其余红球和蓝球保持不变，让号码随数据逐步变化而不是每次完全重选。
+    pub fn evolve_prediction(
+        previous: &PredictionResult,
+        records: &[SsqRecord],
+        algorithm: AlgorithmType,
+        change_count: usize,
+    ) -> PredictionResult {
+        let records = &*Self::exclude_synthetic(records);
+        let red_frequencies = Self::analyze_red_frequency(records, algorithm);
+        let blue_frequencies = Self::analyze_blue_frequency(records, algorithm);
+        let weight_of = |ball: u8| {
+            red_frequencies
+                .iter()
+                .find(|f| f.number == ball)
+                .map_or(0.0, |f| f.weight)
+        };
+
+        let mut red_balls = previous.red_balls.clone();
+        red_balls.sort_by(|a, b| weight_of(*b).partial_cmp(&weight_of(*a)).unwrap());
+        let dropped = red_balls.split_off(red_balls.len().saturating_sub(change_count));
+
+        let mut rng = rand::thread_rng();
+        for removed in dropped {
+            let candidates: Vec<BallFrequency> = red_frequencies
+                .iter()
+                .filter(|f| {
+                    f.weight >= weight_of(removed)
+                        && !previous.red_balls.contains(&f.number)
+                        && !red_balls.contains(&f.number)
+                })
+                .cloned()
+                .collect();
+            let replacement = Self::weighted_random_selection(&candidates, 1, None, &mut rng)
+                .pop()
+                .unwrap_or(removed);
+            red_balls.push(replacement);
+        }
+        red_balls.sort();
+
+        let score = Self::calculate_score(&red_balls, previous.blue_ball, &red_frequencies, &blue_frequencies);
+        PredictionResult {
+            red_balls,
+            blue_ball: previous.blue_ball,
+            score,
+        }
+    }
+
+    /// 权重归一化为概率后的香农熵（比特）
+    ///
+    /// 权重越平均熵越大，均匀分布时为 log2(号码个数)；越集中在少数号码上熵越小。
+    /// 权重全为 0 时返回 0。
+    pub fn distribution_entropy(frequencies: &[BallFrequency]) -> f64 {
+        let total: f64 = frequencies.iter().map(|f| f.weight.max(0.0)).sum();
+        if total <= 0.0 {
+            return 0.0;
+        }
+
+        frequencies
+            .iter()
+            .map(|f| f.weight.max(0.0) / total)
+            .filter(|&p| p > 0.0)
+            .map(|p| -p * p.log2())
+            .sum()
+    }
+
+    /// 各算法的权重分布熵，用于比较算法偏好的集中程度
+    pub fn compare_algorithms(records: &[SsqRecord]) -> Vec<AlgorithmProfile> {
+        let records = &*Self::exclude_synthetic(records);
+        let red_count = (RED_BALL_MAX - RED_BALL_MIN + 1) as f64;
+        let blue_count = (BLUE_BALL_MAX - BLUE_BALL_MIN + 1) as f64;
+
+        AlgorithmType::ALL
+            .into_iter()
+            .map(|algorithm| AlgorithmProfile {
+                algorithm,
+                red_entropy: Self::distribution_entropy(&Self::analyze_red_frequency(records, algorithm)),
+                blue_entropy: Self::distribution_entropy(&Self::analyze_blue_frequency(records, algorithm)),
+                max_red_entropy: red_count.log2(),
+                max_blue_entropy: blue_count.log2(),
+            })
+            .collect()
+    }
+
+    /// 批量对奖：每注号码（红球, 蓝球）对照同一期开奖的中奖等级，顺序与输入一致
+    pub fn evaluate_tickets(tickets: &[(Vec<u8>, u8)], actual: &SsqRecord) -> Vec<Option<u8>> {
+        tickets
+            .iter()
+            .map(|(red_balls, blue_ball)| Self::prize_tier(red_balls, *blue_ball, actual))
+            .collect()
+    }
+
+    /// 按红球和值奇偶分组的蓝球频率：(和值为奇数的各期, 和值为偶数的各期)
+    pub fn blue_by_sum_parity(
+        records: &[SsqRecord],
+        algorithm: AlgorithmType,
+    ) -> (Vec<BallFrequency>, Vec<BallFrequency>) {
+        let records = &*Self::exclude_synthetic(records);
+        let (odd, even): (Vec<SsqRecord>, Vec<SsqRecord>) = records
+            .iter()
+            .cloned()
+            .partition(|record| Parity::of(Self::red_sum(record)) == Parity::Odd);
+
+        (
+            Self::analyze_blue_frequency(&odd, algorithm),
+            Self::analyze_blue_frequency(&even, algorithm),
+        )
+    }
+
+    /// 快速选号：只做一次加权抽取（6 个红球 + 1 个蓝球），不做多轮迭代和去重
+    ///
+    /// 指定 `seed` 时结果可复现。
+    pub fn quick_pick(records: &[SsqRecord], algorithm: AlgorithmType, seed: Option<u64>) -> PredictionResult {
+        let records = &*Self::exclude_synthetic(records);
+        let red_frequencies = Self::analyze_red_frequency(records, algorithm);
+        let blue_frequencies = Self::analyze_blue_frequency(records, algorithm);
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let red_balls = Self::weighted_random_selection(&red_frequencies, 6, None, &mut rng);
+        let blue_ball = Self::weighted_random_selection(&blue_frequencies, 1, None, &mut rng)[0];
+        let score = Self::calculate_score(&red_balls, blue_ball, &red_frequencies, &blue_frequencies);
+
+        PredictionResult {
+            red_balls,
+            blue_ball,
+            score,
+        }
+    }
+
+    /// 逐注对照实际开奖，给出命中红球数、蓝球是否命中和中奖等级，顺序与输入一致
+    pub fn grade_predictions(predictions: &[PredictionResult], actual: &SsqRecord) -> Vec<PredictionGrade> {
+        let actual_reds = actual.red_balls();
+        predictions
+            .iter()
+            .map(|prediction| PredictionGrade {
+                prediction: prediction.clone(),
+                red_matches: prediction
+                    .red_balls
+                    .iter()
+                    .filter(|ball| actual_reds.contains(ball))
+                    .count(),
+                blue_match: prediction.blue_ball == actual.blue_ball,
+                tier: Self::prize_tier(&prediction.red_balls, prediction.blue_ball, actual),
+            })
+            .collect()
+    }
+
+    /// 结果最好的一注：中奖等级越高（数字越小）越好，未中奖的排在最后；等级相同比命中红球数
+    pub fn best_grade(grades: &[PredictionGrade]) -> Option<usize> {
+        grades
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, grade)| (grade.tier.unwrap_or(u8::MAX), std::cmp::Reverse(grade.red_matches)))
+            .map(|(idx, _)| idx)
+    }
+
+    /// 最近 `window` 期的红球出现矩阵：每个红球（1-33）对应一组布尔值，
+    /// 按时间顺序（从早到晚）表示该期是否开出，所有号码的期次顺序一致
+    pub fn recent_presence_matrix(records: &[SsqRecord], window: usize) -> Vec<(u8, Vec<bool>)> {
+        let records = &*Self::exclude_synthetic(records);
+        let start = records.len().saturating_sub(window);
+        let recent: Vec<Vec<u8>> = records[start..].iter().map(|r| r.red_balls()).collect();
+
+        (RED_BALL_MIN..=RED_BALL_MAX)
+            .map(|number| (number, recent.iter().map(|reds| reds.contains(&number)).collect()))
+            .collect()
+    }
+
+    /// "共同遗漏"的红球号码对：两个号码当前都已遗漏至少 `min_omission` 期，且历史上经常同时开出
+    ///
+    /// 得分 = 两个号码历史同时开出的期数 × 两者当前遗漏中较小的一个，
+    /// 即同现越频繁、两者都越久未出，得分越高；从未同时开出的号码对不计入。
+    /// 按得分从高到低排列，没有号码达到遗漏门槛时返回空列表。
+    pub fn co_overdue_pairs(records: &[SsqRecord], min_omission: usize) -> Vec<(RedPair, usize)> {
+        let records = &*Self::exclude_synthetic(records);
+        let overdue: Vec<(u8, usize)> = (RED_BALL_MIN..=RED_BALL_MAX)
+            .map(|number| (number, Self::current_omission(records, number, false)))
+            .filter(|&(_, omission)| omission >= min_omission)
+            .collect();
+        if overdue.len() < 2 {
+            return Vec::new();
+        }
+
+        let cooccurrence = Self::red_cooccurrence(records);
+        let mut pairs = Vec::new();
+        for (i, &(a, omission_a)) in overdue.iter().enumerate() {
+            for &(b, omission_b) in &overdue[i + 1..] {
+                let together = cooccurrence.get(&(a, b)).copied().unwrap_or(0);
+                if together > 0 {
+                    pairs.push(((a, b), together * omission_a.min(omission_b)));
+                }
+            }
+        }
+
+        pairs.sort_by(|x, y| y.1.cmp(&x.1).then(x.0.cmp(&y.0)));
+        pairs
+    }
+
+    /// 红球出现次数相对均匀分布的偏差及卡方检验
+    ///
+    /// 每期开出 6 个红球，公平的情况下每个号码的期望出现次数为 `总期数 × 6/33`。
+    pub fn fairness_deviation(records: &[SsqRecord]) -> FairnessReport {
+        let records = &*Self::exclude_synthetic(records);
+        let pool = (RED_BALL_MAX - RED_BALL_MIN + 1) as usize;
+        let expected = records.len() as f64 * 6.0 / pool as f64;
+
+        // 损坏的本地数据可能含有超出范围的号码，这样的号码不计入
+        let mut counts = [0usize; RED_BALL_MAX as usize + 1];
+        for record in records {
+            for ball in record.red_balls() {
+                if let Some(count) = counts.get_mut(ball as usize) {
+                    *count += 1;
+                }
+            }
+        }
+
+        let deviations: Vec<(u8, f64)> = (RED_BALL_MIN..=RED_BALL_MAX)
+            .map(|number| (number, counts[number as usize] as f64 - expected))
+            .collect();
+        let chi_square = if expected > 0.0 {
+            deviations.iter().map(|(_, d)| d * d / expected).sum()
+        } else {
+            0.0
+        };
+
+        FairnessReport {
+            deviations,
+            expected,
+            chi_square,
+            degrees_of_freedom: pool - 1,
+            critical_value: CHI_SQUARE_CRITICAL_32,
+        }
+    }
+
+    /// 完全随机的机选号码：不参考任何历史数据，生成 `count` 注互不相同的合法号码，得分均为 0
+    ///
+    /// 指定 `seed` 时结果可复现。
+    pub fn random_tickets(count: usize, seed: Option<u64>) -> Vec<PredictionResult> {
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let red_pool = (RED_BALL_MAX - RED_BALL_MIN + 1) as usize;
+
+        let mut seen = HashSet::new();
+        let mut tickets = Vec::with_capacity(count);
+        while tickets.len() < count {
+            let mut red_balls: Vec<u8> = rand::seq::index::sample(&mut rng, red_pool, 6)
+                .into_iter()
+                .map(|idx| RED_BALL_MIN + idx as u8)
+                .collect();
+            red_balls.sort();
+            let blue_ball = rng.gen_range(BLUE_BALL_MIN..=BLUE_BALL_MAX);
+
+            if seen.insert((red_balls.clone(), blue_ball)) {
+                tickets.push(PredictionResult {
+                    red_balls,
+                    blue_ball,
+                    score: 0.0,
+                });
+            }
+        }
+        tickets
+    }
+
+    /// 最常同时开出的红球三元组，按次数从多到少取前 `top_n` 个（次数相同按号码排序）
+    ///
+    /// 每期 6 个红球有 C(6,3)=20 个三元组，三元组内按从小到大排列作为统计键。
+    pub fn top_red_triplets(records: &[SsqRecord], top_n: usize) -> Vec<([u8; 3], usize)> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut counts: HashMap<[u8; 3], usize> = HashMap::new();
+        for record in records {
+            let reds = record.sorted_red_balls();
+            for i in 0..reds.len() {
+                for j in (i + 1)..reds.len() {
+                    for k in (j + 1)..reds.len() {
+                        *counts.entry([reds[i], reds[j], reds[k]]).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        let mut triplets: Vec<([u8; 3], usize)> = counts.into_iter().collect();
+        triplets.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        triplets.truncate(top_n);
+        triplets
+    }
+
+    /// 遗漏压力：当前遗漏 - 平均间隔，正数表示比平时更久未出
+    ///
+    /// 出现次数不足以计算平均间隔的号码压力记为 0。按压力从高到低排列。
+    pub fn omission_pressure(records: &[SsqRecord], is_blue: bool) -> Vec<(u8, f64)> {
+        let records = &*Self::exclude_synthetic(records);
+        let (min, max) = Self::ball_range(is_blue);
+        let mut pressure: Vec<(u8, f64)> = (min..=max)
+            .map(|number| {
+                let value = Self::average_interval(records, number, is_blue).map_or(0.0, |average| {
+                    Self::current_omission(records, number, is_blue) as f64 - average
+                });
+                (number, value)
+            })
+            .collect();
+
+        pressure.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(a.0.cmp(&b.0)));
+        pressure
+    }
+
+    /// 用自助法估计每个号码权重的 90% 区间
+    ///
+    /// 对历史记录有放回地重采样 `iterations` 次（每次与原数据同样大小），
+    /// 每次重新做频率分析，统计各号码权重的均值和 5%/95% 分位数。
+    /// 区间越宽，说明冷热排名受样本偶然性影响越大，越不可靠。指定 `seed` 时结果可复现。
+    pub fn bootstrap_weights(
+        records: &[SsqRecord],
+        algorithm: AlgorithmType,
+        is_blue: bool,
+        iterations: usize,
+        seed: Option<u64>,
+    ) -> Vec<WeightInterval> {
+        let records = &*Self::exclude_synthetic(records);
+        let (min, max) = Self::ball_range(is_blue);
+        let mut samples: Vec<Vec<f64>> = vec![Vec::with_capacity(iterations); max as usize + 1];
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        if !records.is_empty() {
+            for _ in 0..iterations {
+                let resample: Vec<SsqRecord> = (0..records.len())
+                    .map(|_| records[rng.gen_range(0..records.len())].clone())
+                    .collect();
+                let frequencies = if is_blue {
+                    Self::analyze_blue_frequency(&resample, algorithm)
+                } else {
+                    Self::analyze_red_frequency(&resample, algorithm)
+                };
+                for f in frequencies {
+                    samples[f.number as usize].push(f.weight);
+                }
+            }
+        }
+
+        (min..=max)
+            .map(|number| {
+                let weights = &mut samples[number as usize];
+                weights.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let percentile = |p: f64| -> f64 {
+                    if weights.is_empty() {
+                        return 0.0;
+                    }
+                    weights[((weights.len() - 1) as f64 * p).round() as usize]
+                };
+                WeightInterval {
+                    number,
+                    mean: if weights.is_empty() {
+                        0.0
+                    } else {
+                        weights.iter().sum::<f64>() / weights.len() as f64
+                    },
+                    p5: percentile(0.05),
+                    p95: percentile(0.95),
+                }
+            })
+            .collect()
+    }
+
+    /// 号码历史上最长的连续未出现区间
+    ///
+    /// 与最大遗漏的计算相同，但同时给出区间所在的期号；长度相同时取最早的一段。
+    /// 每期都出现时长度为 0，从未出现时为总期数。
+    pub fn longest_absence(records: &[SsqRecord], number: u8, is_blue: bool) -> AbsenceRun {
+        let records = &*Self::exclude_synthetic(records);
+        let mut longest = (0usize, 0usize);
+        let mut start = 0;
+        for (idx, record) in records.iter().enumerate() {
+            if Self::contains_number(record, number, is_blue) {
+                start = idx + 1;
+            } else if idx + 1 - start > longest.1 - longest.0 {
+                longest = (start, idx + 1);
+            }
+        }
+
+        let (first, end) = longest;
+        let (first_issue, last_issue) = if end > first {
+            (Some(records[first].issue.clone()), Some(records[end - 1].issue.clone()))
+        } else {
+            (None, None)
+        };
+        AbsenceRun {
+            number,
+            length: end - first,
+            first_issue,
+            last_issue,
+        }
+    }
+
+    /// 模拟连续 `draws` 期都投注同一组号码的盈亏
+    ///
+    /// 假设：
+    /// - 每一期的开奖号码从历史开奖中等概率有放回地抽取，即未来的开奖与历史分布相同；
+    /// - 奖金按固定奖额表 `prize_amount` 计算，不考虑浮动奖金和奖池；
+    /// - 每期每注成本为 `TICKET_PRICE`。
+    ///
+    /// 重复模拟 `PLAY_SIMULATION_TRIALS` 次，给出净收益的均值和方差。指定 `seed` 时结果可复现。
+    pub fn simulate_play(
+        predictions: &[PredictionResult],
+        records: &[SsqRecord],
+        draws: usize,
+        seed: Option<u64>,
+    ) -> PlaySimulation {
+        let records = &*Self::exclude_synthetic(records);
+        let total_cost = (predictions.len() * draws) as u64 * TICKET_PRICE;
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let trials = if records.is_empty() { 0 } else { PLAY_SIMULATION_TRIALS };
+        let winnings: Vec<f64> = (0..trials)
+            .map(|_| {
+                let total: u64 = (0..draws)
+                    .map(|_| {
+                        let actual = &records[rng.gen_range(0..records.len())];
+                        predictions
+                            .iter()
+                            .filter_map(|p| Self::prize_tier(&p.red_balls, p.blue_ball, actual))
+                            .map(Self::prize_amount)
+                            .sum::<u64>()
+                    })
+                    .sum();
+                total as f64
+            })
+            .collect();
+
+        // 成本固定，净收益的方差就是奖金的方差
+        let (mean, std) = mean_and_std(&winnings);
+        let expected_winnings = mean.unwrap_or(0.0);
+        let net_variance = std.map_or(0.0, |std| std * std);
+        let profitable = winnings.iter().filter(|&&w| w > total_cost as f64).count();
+
+        PlaySimulation {
+            draws,
+            tickets_per_draw: predictions.len(),
+            trials,
+            total_cost,
+            expected_winnings,
+            expected_net: expected_winnings - total_cost as f64,
+            net_variance,
+            profit_probability: if trials == 0 {
+                0.0
+            } else {
+                profitable as f64 / trials as f64
+            },
+        }
+    }
+
+    /// 按开奖日是星期几分组，按周一到周日排列；日期无法解析的记录单独放在最后一组
+    pub fn draws_by_weekday(records: &[SsqRecord]) -> Vec<(Option<Weekday>, Vec<SsqRecord>)> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut groups: Vec<(Option<Weekday>, Vec<SsqRecord>)> = Vec::new();
+        for record in records {
+            let weekday = record.get_date().map(|date| date.weekday());
+            match groups.iter_mut().find(|(w, _)| *w == weekday) {
+                Some((_, group)) => group.push(record.clone()),
+                None => groups.push((weekday, vec![record.clone()])),
+            }
+        }
+
+        groups.sort_by_key(|(weekday, _)| weekday.map_or(7, |w| w.num_days_from_monday()));
+        groups
+    }
+
+    /// 每个开奖日分别统计的红球频率，用来检验不同开奖日的号码是否有差异
+    pub fn frequency_by_weekday(records: &[SsqRecord], algorithm: AlgorithmType) -> Vec<WeekdayFrequency> {
+        let records = &*Self::exclude_synthetic(records);
+        Self::draws_by_weekday(records)
+            .into_iter()
+            .map(|(weekday, group)| WeekdayFrequency {
+                weekday,
+                draws: group.len(),
+                red_frequency: Self::analyze_red_frequency(&group, algorithm),
+            })
+            .collect()
+    }
+
+    /// 检查数据质量：号码越界、红球重复、日期无效（无法解析或晚于 `today`），
+    /// 以及和值偏离历史均值超过 `ANOMALY_SUM_SIGMAS` 个标准差的记录
+    ///
+    /// 和值的均值和标准差只用号码合法的记录计算，避免坏数据拉偏正常范围。
+    pub fn detect_anomalies(records: &[SsqRecord], today: NaiveDate) -> Vec<AnomalyReport> {
+        let mut anomalies = Vec::new();
+        let mut valid = Vec::new();
+
+        for record in records {
+            let mut report = |kind, detail: String| {
+                anomalies.push(AnomalyReport {
+                    issue: record.issue.clone(),
+                    kind,
+                    detail,
+                });
+            };
+
+            let reds = record.red_balls();
+            let mut well_formed = true;
+            for &ball in &reds {
+                if !(RED_BALL_MIN..=RED_BALL_MAX).contains(&ball) {
+                    report(AnomalyKind::OutOfRange, format!("红球超出范围: {}", ball));
+                    well_formed = false;
+                }
+            }
+            if !(BLUE_BALL_MIN..=BLUE_BALL_MAX).contains(&record.blue_ball) {
+                report(AnomalyKind::OutOfRange, format!("蓝球超出范围: {}", record.blue_ball));
+                well_formed = false;
+            }
+            for (idx, ball) in reds.iter().enumerate() {
+                if reds[..idx].contains(ball) && !reds[idx + 1..].contains(ball) {
+                    report(AnomalyKind::DuplicateRed, format!("红球重复: {}", ball));
+                    well_formed = false;
+                }
+            }
+            match record.get_date() {
+                None => report(AnomalyKind::InvalidDate, format!("无法解析的日期: {}", record.date)),
+                Some(date) if date > today => {
+                    report(AnomalyKind::InvalidDate, format!("日期晚于今天: {}", record.date))
+                }
+                Some(_) => {}
+            }
+
+            if well_formed {
+                valid.push(record);
+            }
+        }
+
+        let sums: Vec<f64> = valid.iter().map(|r| Self::red_sum(r) as f64).collect();
+        if let (Some(mean), Some(std)) = mean_and_std(&sums) {
+            let limit = ANOMALY_SUM_SIGMAS * std;
+            for record in valid {
+                let sum = Self::red_sum(record);
+                if (sum as f64 - mean).abs() > limit {
+                    anomalies.push(AnomalyReport {
+                        issue: record.issue.clone(),
+                        kind: AnomalyKind::ExtremeSum,
+                        detail: format!("和值 {} 远离历史均值 {:.1}", sum, mean),
+                    });
+                }
+            }
+        }
+
+        anomalies
+    }
+
+    /// 旋转矩阵：用号码池中的红球组成多注投注，蓝球都用 `blue`
+    ///
+    /// - `guarantee` 为 `None` 或 6 时是复式全包，返回池中任选 6 个的全部 C(n, 6) 注；
+    /// - `guarantee` 为 `g`（1-5）时是缩水矩阵：只要开出的红球中有 `g` 个在号码池内，
+    ///   至少有一注命中其中全部 `g` 个。用贪心法每次选覆盖最多未覆盖 `g` 元组的一注，
+    ///   注数不一定是理论最少，但保证条件一定满足。
+    ///
+    /// 号码池需已校验（大小在 `WHEEL_POOL_SIZES` 内且互不重复），每注得分均为 0。
+    pub fn wheel(pool: &[u8], blue: u8, guarantee: Option<u8>) -> Vec<PredictionResult> {
+        let mut pool = pool.to_vec();
+        pool.sort();
+        let tickets = combinations(&pool, 6);
+
+        let chosen = match guarantee {
+            Some(g) if g < 6 => {
+                // 每注包含的 g 元组在全部 g 元组中的下标
+                let subsets = combinations(&pool, g as usize);
+                let subset_index: HashMap<&[u8], usize> =
+                    subsets.iter().enumerate().map(|(idx, s)| (s.as_slice(), idx)).collect();
+                let covers: Vec<Vec<usize>> = tickets
+                    .iter()
+                    .map(|ticket| {
+                        combinations(ticket, g as usize)
+                            .iter()
+                            .map(|s| subset_index[s.as_slice()])
+                            .collect()
+                    })
+                    .collect();
+
+                let mut covered = vec![false; subsets.len()];
+                let mut remaining = subsets.len();
+                let mut chosen = Vec::new();
+                while remaining > 0 {
+                    let (best, gain) = covers
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, cover)| (idx, cover.iter().filter(|&&s| !covered[s]).count()))
+                        .fold((0, 0), |best, candidate| if candidate.1 > best.1 { candidate } else { best });
+                    if gain == 0 {
+                        break;
+                    }
+                    for &s in &covers[best] {
+                        if !covered[s] {
+                            covered[s] = true;
+                            remaining -= 1;
+                        }
+                    }
+                    chosen.push(tickets[best].clone());
+                }
+                chosen.sort();
+                chosen
+            }
+            _ => tickets,
+        };
+
+        chosen
+            .into_iter()
+            .map(|red_balls| PredictionResult {
+                red_balls,
+                blue_ball: blue,
+                score: 0.0,
+            })
+            .collect()
+    }
+
+    /// 一组投注号码覆盖了多少不同的红球和蓝球，以及两两之间最多有几个红球相同，
+    /// 用于检查买的几注是否过于重复
+    pub fn ticket_set_coverage(tickets: &[(Vec<u8>, u8)]) -> CoverageReport {
+        let reds: HashSet<u8> = tickets.iter().flat_map(|(reds, _)| reds.iter().copied()).collect();
+        let blues: HashSet<u8> = tickets.iter().map(|&(_, blue)| blue).collect();
+
+        let mut max_overlap = 0;
+        let mut max_overlap_pair = None;
+        for (i, (a, _)) in tickets.iter().enumerate() {
+            for (j, (b, _)) in tickets.iter().enumerate().skip(i + 1) {
+                let overlap = a.iter().filter(|ball| b.contains(ball)).count();
+                if max_overlap_pair.is_none() || overlap > max_overlap {
+                    max_overlap = overlap;
+                    max_overlap_pair = Some((i, j));
+                }
+            }
+        }
+
+        CoverageReport {
+            distinct_reds: reds.len(),
+            distinct_blues: blues.len(),
+            uncovered_reds: (RED_BALL_MIN..=RED_BALL_MAX)
+                .filter(|ball| !reds.contains(ball))
+                .collect(),
+            max_overlap,
+            max_overlap_pair,
+        }
+    }
+
+    /// 号码在最近 `window` 期内的出现率随时间的变化，每个点以窗口最后一期的期号标记
+    ///
+    /// 窗口滑动时只加上新进入的一期、减去移出的一期。`window` 大于记录数时返回空序列。
+    fn rolling_frequency(records: &[SsqRecord], number: u8, is_blue: bool, window: usize) -> Vec<(String, f64)> {
+        if window == 0 || window > records.len() {
+            return Vec::new();
+        }
+
+        let hits: Vec<bool> = records
+            .iter()
+            .map(|record| Self::contains_number(record, number, is_blue))
+            .collect();
+        let mut count = hits[..window].iter().filter(|&&hit| hit).count();
+        let mut series = Vec::with_capacity(records.len() - window + 1);
+        series.push((records[window - 1].issue.clone(), count as f64 / window as f64));
+        for idx in window..records.len() {
+            count = count + hits[idx] as usize - hits[idx - window] as usize;
+            series.push((records[idx].issue.clone(), count as f64 / window as f64));
+        }
+        series
+    }
+
+    /// 蓝球的滚动出现率，用于蓝球走势图；蓝球只有 16 个号码，序列波动比红球大
+    pub fn blue_rolling_frequency(records: &[SsqRecord], blue_number: u8, window: usize) -> Vec<(String, f64)> {
+        let records = &*Self::exclude_synthetic(records);
+        Self::rolling_frequency(records, blue_number, true, window)
+    }
+
+    /// 只统计上一期蓝球为 `prev_blue` 的那些期的红球频率，用来检验前一期蓝球与下一期红球是否相关
+    ///
+    /// 权重按出现率计算（同"贴近真实"算法）。`prev_blue` 从未出现在非最新一期时，所有号码的次数和权重都为 0。
+    pub fn reds_given_prev_blue(records: &[SsqRecord], prev_blue: u8) -> Vec<BallFrequency> {
+        let records = &*Self::exclude_synthetic(records);
+        let following: Vec<SsqRecord> = records
+            .windows(2)
+            .filter(|pair| pair[0].blue_ball == prev_blue)
+            .map(|pair| pair[1].clone())
+            .collect();
+        Self::analyze_red_frequency(&following, AlgorithmType::Realistic)
+    }
+
+    /// 号码温度：0 表示极冷，100 表示极热，按号码顺序返回
+    ///
+    /// 三个分项都先归一化到 0-1，再按 `weights` 加权平均后乘以 100：
+    /// - 出现率：衰减系数 `TEMPERATURE_DECAY` 下的出现率除以所有号码中的最大值；
+    /// - 遗漏：`1 - 当前遗漏 / 所有号码中最大的当前遗漏`；
+    /// - 走势：上升为 1，平稳为 0.5，下降为 0。
+    pub fn number_temperature(
+        records: &[SsqRecord],
+        is_blue: bool,
+        weights: &TemperatureWeights,
+    ) -> Vec<(u8, f64)> {
+        let records = &*Self::exclude_synthetic(records);
+        let (min, max) = Self::ball_range(is_blue);
+        let mut frequencies =
+            Self::analyze_frequency_with_decay(records, AlgorithmType::Realistic, is_blue, TEMPERATURE_DECAY);
+        frequencies.sort_by_key(|f| f.number);
+        let max_weight = frequencies.iter().map(|f| f.weight).fold(0.0, f64::max);
+
+        let omissions: Vec<usize> = (min..=max)
+            .map(|number| Self::current_omission(records, number, is_blue))
+            .collect();
+        let max_omission = omissions.iter().copied().max().unwrap_or(0);
+
+        let total_weight = weights.frequency + weights.omission + weights.trend;
+        (min..=max)
+            .zip(frequencies.iter().zip(&omissions))
+            .map(|(number, (frequency, &omission))| {
+                let frequency_score = if max_weight > 0.0 { frequency.weight / max_weight } else { 0.0 };
+                let omission_score = if max_omission > 0 {
+                    1.0 - omission as f64 / max_omission as f64
+                } else {
+                    1.0
+                };
+                let trend_score = match Self::number_trend(records, number, is_blue) {
+                    NumberTrend::Rising => 1.0,
+                    NumberTrend::Stable => 0.5,
+                    NumberTrend::Falling => 0.0,
+                };
+
+                let combined = weights.frequency * frequency_score
+                    + weights.omission * omission_score
+                    + weights.trend * trend_score;
+                (number, combined / total_weight * 100.0)
+            })
+            .collect()
+    }
+
+    /// 按"蓝球 = 红球和值 % 16 + 1"的规则由红球推出的蓝球
+    pub fn blue_for_sum(red_balls: &[u8]) -> u8 {
+        let sum: u32 = red_balls.iter().map(|&ball| ball as u32).sum();
+        (sum % BLUE_BALL_MAX as u32) as u8 + 1
+    }
+
+    /// 蓝球与红球和值的关系：相关系数以及"蓝球 = 和值 % 16 + 1"规则的命中率
+    ///
+    /// 红球和蓝球是两个独立摇奖机开出的，理论上没有任何关系；说明文字会如实给出关系有多弱。
+    pub fn blue_sum_relationship(records: &[SsqRecord]) -> RelationshipModel {
+        let records = &*Self::exclude_synthetic(records);
+        let sums: Vec<f64> = records.iter().map(|r| Self::red_sum(r) as f64).collect();
+        let blues: Vec<f64> = records.iter().map(|r| r.blue_ball as f64).collect();
+
+        let correlation = match (mean_and_std(&sums), mean_and_std(&blues)) {
+            ((Some(sum_mean), Some(sum_std)), (Some(blue_mean), Some(blue_std)))
+                if records.len() >= 2 && sum_std > 0.0 && blue_std > 0.0 =>
+            {
+                let covariance = sums
+                    .iter()
+                    .zip(&blues)
+                    .map(|(s, b)| (s - sum_mean) * (b - blue_mean))
+                    .sum::<f64>()
+                    / records.len() as f64;
+                Some(covariance / (sum_std * blue_std))
+            }
+            _ => None,
+        };
+
+        let mod_rule_hits = records
+            .iter()
+            .filter(|r| Self::blue_for_sum(&r.red_balls()) == r.blue_ball)
+            .count();
+        let mod_rule_hit_rate = if records.is_empty() {
+            0.0
+        } else {
+            mod_rule_hits as f64 / records.len() as f64
+        };
+        let expected_hit_rate = 1.0 / (BLUE_BALL_MAX - BLUE_BALL_MIN + 1) as f64;
+
+        let assessment = match correlation {
+            None => "数据不足，无法判断".to_string(),
+            Some(r) if r.abs() < 0.1 => format!(
+                "相关系数 {:.3}，几乎没有线性关系；取模规则命中率 {:.1}%，随机为 {:.1}%",
+                r,
+                mod_rule_hit_rate * 100.0,
+                expected_hit_rate * 100.0
+            ),
+            Some(r) => format!(
+                "相关系数 {:.3}，也只能解释 {:.1}% 的蓝球变化，很可能是样本偶然性；取模规则命中率 {:.1}%，随机为 {:.1}%",
+                r,
+                r * r * 100.0,
+                mod_rule_hit_rate * 100.0,
+                expected_hit_rate * 100.0
+            ),
+        };
+
+        RelationshipModel {
+            samples: records.len(),
+            correlation,
+            mod_rule_hits,
+            mod_rule_hit_rate,
+            expected_hit_rate,
+            assessment,
+        }
+    }
+
+    /// 一注号码与若干期开奖的差异度（0-1）：1 减去平均重合度，
+    /// 每期的重合度为 `(相同红球个数 + 蓝球是否相同) / 7`；没有开奖记录时为 1
+    pub fn dissimilarity(red_balls: &[u8], blue_ball: u8, draws: &[SsqRecord]) -> f64 {
+        if draws.is_empty() {
+            return 1.0;
+        }
+
+        let overlap: f64 = draws
+            .iter()
+            .map(|draw| {
+                let reds = draw.red_balls();
+                let shared = red_balls.iter().filter(|ball| reds.contains(ball)).count();
+                (shared + (draw.blue_ball == blue_ball) as usize) as f64 / 7.0
+            })
+            .sum();
+        1.0 - overlap / draws.len() as f64
+    }
+
+    /// 号码还有几期"该出"：平均间隔减去当前遗漏（不小于 0）
+    ///
+    /// 可信度为 `1 / (1 + 变异系数)`，变异系数 = 间隔标准差 / 平均间隔，间隔越稳定越接近 1；
+    /// 间隔少于 `MIN_FORECAST_INTERVALS` 个时为 0。开奖相互独立，这只是对历史间隔的描述。
+    pub fn due_forecast(records: &[SsqRecord], number: u8, is_blue: bool) -> DueForecast {
+        let records = &*Self::exclude_synthetic(records);
+        let indices = Self::appearance_indices(records, number, is_blue);
+        let intervals: Vec<f64> = indices.windows(2).map(|pair| (pair[1] - pair[0]) as f64).collect();
+        let (average_interval, interval_std) = mean_and_std(&intervals);
+        let current_omission = Self::current_omission(records, number, is_blue);
+
+        let confidence = match (average_interval, interval_std) {
+            (Some(mean), Some(std)) if intervals.len() >= MIN_FORECAST_INTERVALS && mean > 0.0 => {
+                1.0 / (1.0 + std / mean)
+            }
+            _ => 0.0,
+        };
+
+        DueForecast {
+            number,
+            average_interval,
+            interval_std,
+            current_omission,
+            draws_until_due: average_interval.map(|mean| (mean - current_omission as f64).max(0.0)),
+            confidence,
+        }
+    }
+
+    /// 假如从第 `BANKROLL_WARMUP_DRAWS + 1` 期起每期都买 `tickets_per_draw` 注预测号码，累计盈亏会怎样
+    ///
+    /// 逐期只用之前的数据生成预测（与回测相同，使用默认选项），每注 `TICKET_PRICE` 元，
+    /// 按固定奖额计算奖金。给出最终盈亏、最大回撤和单期最好/最差的结果。
+    ///
+    /// 预测用 `entropy` 作为随机种子，没有给出时随机生成；报告中带回实际使用的熵，
+    /// 用同一字符串再次计算可以得到完全相同的结果。
+    pub fn historical_bankroll(
+        records: &[SsqRecord],
+        algorithm: AlgorithmType,
+        tickets_per_draw: usize,
+        entropy: Option<&str>,
+    ) -> BankrollReport {
+        let records = &*Self::exclude_synthetic(records);
+        let entropy = match entropy {
+            Some(entropy) => entropy.to_string(),
+            None => format!("{:016x}", rand::thread_rng().gen::<u64>()),
+        };
+        let options = PredictionOptions {
+            entropy: Some(entropy.clone()),
+            ..Default::default()
+        };
+        let backtest = Self::backtest_from(
+            records,
+            algorithm,
+            &options,
+            BANKROLL_WARMUP_DRAWS.min(records.len()),
+            tickets_per_draw,
+        );
+
+        let nets: Vec<(String, i64)> = backtest
+            .draws
+            .iter()
+            .map(|draw| (draw.issue.clone(), draw.winnings as i64 - draw.cost as i64))
+            .collect();
+
+        let mut balance: i64 = 0;
+        let mut peak: i64 = 0;
+        let mut max_drawdown = 0;
+        let mut balance_history = Vec::with_capacity(nets.len());
+        for (issue, net) in &nets {
+            balance += net;
+            peak = peak.max(balance);
+            max_drawdown = max_drawdown.max(peak - balance);
+            balance_history.push((issue.clone(), balance));
+        }
+        let best_draw = nets.iter().max_by_key(|(_, net)| *net).cloned();
+        let worst_draw = nets.iter().min_by_key(|(_, net)| *net).cloned();
+
+        BankrollReport {
+            draws: nets.len(),
+            tickets_per_draw,
+            total_cost: backtest.total_cost,
+            total_winnings: backtest.total_winnings,
+            final_balance: balance,
+            max_drawdown,
+            best_draw,
+            worst_draw,
+            balance_history,
+            entropy,
+        }
+    }
+
+    /// 红球"伴侣"关系图的边：同时出现次数不少于 `min_cooccurrence` 的号码对 (小号, 大号, 次数)
+    ///
+    /// 按 (小号, 大号) 排序，保证同一份数据每次输出的边顺序一致，便于前端稳定布局。
+    pub fn companionship_graph(records: &[SsqRecord], min_cooccurrence: usize) -> Vec<(u8, u8, usize)> {
+        let records = &*Self::exclude_synthetic(records);
+        let mut edges: Vec<(u8, u8, usize)> = Self::red_cooccurrence(records)
+            .into_iter()
+            .filter(|&(_, count)| count >= min_cooccurrence)
+            .map(|((a, b), count)| (a, b, count))
+            .collect();
+        edges.sort();
+        edges
+    }
+
+    /// 只统计期号在 `start..=end` 之间的红球频率（按年份和序号比较，两位、四位年份的期号可以混用）
+    ///
+    /// 没有期号落在区间内时，所有号码的次数和权重都为 0。
+    pub fn analyze_red_frequency_by_issue_range(
+        records: &[SsqRecord],
+        start: IssueNumber,
+        end: IssueNumber,
+        algorithm: AlgorithmType,
+    ) -> Vec<BallFrequency> {
+        let records = &*Self::exclude_synthetic(records);
+        let in_range: Vec<SsqRecord> = records
+            .iter()
+            .filter(|record| record.issue_number().is_some_and(|issue| (start..=end).contains(&issue)))
+            .cloned()
+            .collect();
+        Self::analyze_red_frequency(&in_range, algorithm)
+    }
+
+    /// 最新一期开奖的"趣闻"：久未出现的号码回归、和值/跨度创历史或当年极值、
+    /// 少见的奇偶比、与历史某期红球完全相同或与上一期重复较多。少于两期数据时为空
+    pub fn latest_draw_highlights(records: &[SsqRecord]) -> Vec<String> {
+        let records = &*Self::exclude_synthetic(records);
+        let Some((latest, history)) = records.split_last() else {
+            return Vec::new();
+        };
+        if history.is_empty() {
+            return Vec::new();
+        }
+        let mut highlights = Vec::new();
+
+        // 久未出现的号码
+        for ball in latest.sorted_red_balls() {
+            let omission = Self::current_omission(history, ball, false);
+            if omission >= HIGHLIGHT_RED_DROUGHT {
+                highlights.push(format!("红球 {:02} 时隔 {} 期再次开出", ball, omission));
+            }
+        }
+        let blue_omission = Self::current_omission(history, latest.blue_ball, true);
+        if blue_omission >= HIGHLIGHT_BLUE_DROUGHT {
+            highlights.push(format!(
+                "蓝球 {:02} 时隔 {} 期再次开出",
+                latest.blue_ball, blue_omission
+            ));
+        }
+
+        // 和值、跨度的极值：先比全部历史，不是历史极值时再比当年
+        let year = latest.issue_number().map(|issue| issue.year);
+        let same_year: Vec<&SsqRecord> = history
+            .iter()
+            .filter(|r| year.is_some() && r.issue_number().map(|issue| issue.year) == year)
+            .collect();
+        type Metric = fn(&SsqRecord) -> u32;
+        let metrics: [(&str, Metric); 2] =
+            [("和值", Self::red_sum), ("跨度", |r| Self::red_span(r) as u32)];
+        for (name, metric) in metrics {
+            let value = metric(latest);
+            let all_max = history.iter().map(metric).max().unwrap_or(0);
+            let all_min = history.iter().map(metric).min().unwrap_or(0);
+            if value > all_max {
+                highlights.push(format!("{} {} 为历史最高", name, value));
+            } else if value < all_min {
+                highlights.push(format!("{} {} 为历史最低", name, value));
+            } else if !same_year.is_empty() {
+                if value > same_year.iter().map(|r| metric(r)).max().unwrap_or(0) {
+                    highlights.push(format!("{} {} 为今年最高", name, value));
+                } else if value < same_year.iter().map(|r| metric(r)).min().unwrap_or(0) {
+                    highlights.push(format!("{} {} 为今年最低", name, value));
+                }
+            }
+        }
+
+        // 少见的奇偶比
+        let odd = Self::odd_count(latest);
+        let share = history.iter().filter(|r| Self::odd_count(r) == odd).count() as f64
+            / history.len() as f64;
+        if share < HIGHLIGHT_RARE_SHARE {
+            highlights.push(format!(
+                "奇偶比 {}:{} 少见，历史上只占 {:.1}%",
+                odd,
+                6 - odd,
+                share * 100.0
+            ));
+        }
+
+        // 与历史红球组合重复
+        let reds = latest.sorted_red_balls();
+        if let Some(same) = history.iter().rev().find(|r| r.sorted_red_balls() == reds) {
+            highlights.push(format!("红球组合与第 {} 期完全相同", same.issue));
+        } else if let Some(previous) = history.last() {
+            let previous_reds = previous.red_balls();
+            let shared = reds.iter().filter(|ball| previous_reds.contains(ball)).count();
+            if shared >= 3 {
+                highlights.push(format!("与上一期重复 {} 个红球", shared));
+            }
+        }
+
+        highlights
+    }
+
+    /// 熵字符串的 SHA-256，作为 ChaCha20 的 32 字节种子
+    pub fn entropy_seed(entropy: &str) -> [u8; 32] {
+        Sha256::digest(entropy.as_bytes()).into()
+    }
+
+    /// 用用户给出的熵字符串生成可验证的预测，并返回实际使用的种子以便公布。
+    /// 这是为了透明，号码的中奖概率与普通预测完全相同
+    ///
+    /// `entropy` 覆盖 `options.entropy`，调用方不必再设置。
+    pub fn fair_predictions(
+        records: &[SsqRecord],
+        red_algorithm: AlgorithmType,
+        blue_algorithm: AlgorithmType,
+        options: &PredictionOptions,
+        entropy: &str,
+    ) -> FairPredictions {
+        let options = PredictionOptions {
+            entropy: Some(entropy.to_string()),
+            ..options.clone()
+        };
+        let seed = Self::entropy_seed(entropy)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        FairPredictions {
+            seed,
+            predictions: Self::generate_predictions(records, red_algorithm, blue_algorithm, &options),
+        }
+    }
+}
+
+/// 已确定中心和宽度的和值/跨度高斯因子
+struct ShapeGaussian {
+    sum_center: f64,
+    sum_width: f64,
+    span_center: f64,
+    span_width: f64,
+}
+
+impl ShapeGaussian {
+    /// 用历史均值和标准差补全未指定的参数；没有历史数据且参数不全时不启用
+    fn resolve(records: &[SsqRecord], penalty: &ShapePenalty) -> Option<Self> {
+        let sums: Vec<f64> = records.iter().map(|r| Analyzer::red_sum(r) as f64).collect();
+        let spans: Vec<f64> = records.iter().map(|r| Analyzer::red_span(r) as f64).collect();
+        let (sum_mean, sum_std) = mean_and_std(&sums);
+        let (span_mean, span_std) = mean_and_std(&spans);
+
+        Some(Self {
+            sum_center: penalty.sum_center.or(sum_mean)?,
+            sum_width: penalty.sum_width.or(sum_std.map(|s| s.max(MIN_SHAPE_WIDTH)))?,
+            span_center: penalty.span_center.or(span_mean)?,
+            span_width: penalty.span_width.or(span_std.map(|s| s.max(MIN_SHAPE_WIDTH)))?,
+        })
+    }
+
+    /// `exp(-(x-μ)²/2σ²)` 形式的因子，和值与跨度的因子相乘，取值 (0, 1]
+    fn factor(&self, red_balls: &[u8]) -> f64 {
+        let sum: f64 = red_balls.iter().map(|&ball| ball as f64).sum();
+        let max = red_balls.iter().max().copied().unwrap_or(0);
+        let min = red_balls.iter().min().copied().unwrap_or(0);
+        let span = (max - min) as f64;
+
+        gaussian(sum, self.sum_center, self.sum_width) * gaussian(span, self.span_center, self.span_width)
+    }
+}
+
+fn gaussian(x: f64, center: f64, width: f64) -> f64 {
+    (-(x - center).powi(2) / (2.0 * width * width)).exp()
+}
+
+/// 组合数 C(n, k)
+fn binomial(n: u64, k: u64) -> u64 {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+/// 从 `items` 中取 `k` 个的全部组合，按字典序排列
+fn combinations(items: &[u8], k: usize) -> Vec<Vec<u8>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+    if items.len() < k {
+        return Vec::new();
+    }
+
+    let mut result = Vec::new();
+    for (idx, &first) in items.iter().enumerate() {
+        for mut rest in combinations(&items[idx + 1..], k - 1) {
+            rest.insert(0, first);
+            result.push(rest);
+        }
+    }
+    result
+}
+
+/// 均值和总体标准差，空数据返回 `None`
+fn mean_and_std(values: &[f64]) -> (Option<f64>, Option<f64>) {
+    if values.is_empty() {
+        return (None, None);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    (Some(mean), Some(variance.sqrt()))
+}
+
+/// 增量频率统计器
+///
+/// 维护滑动窗口内每个号码的出现次数，新增或移除一期都是 O(1)，
+/// 适合逐期追加数据的场景，无需每次重新统计全部历史。
+#[derive(Debug, Clone)]
+pub struct FrequencyTracker {
+    /// 窗口内的记录（红球, 蓝球），按时间顺序
+    window: VecDeque<([u8; 6], u8)>,
+    red_counts: [usize; RED_BALL_MAX as usize + 1],
+    blue_counts: [usize; BLUE_BALL_MAX as usize + 1],
+}
+
+impl Default for FrequencyTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrequencyTracker {
+    pub fn new() -> Self {
+        Self {
+            window: VecDeque::new(),
+            red_counts: [0; RED_BALL_MAX as usize + 1],
+            blue_counts: [0; BLUE_BALL_MAX as usize + 1],
+        }
+    }
+
+    /// 当前窗口内的期数
+    pub fn len(&self) -> usize {
+        self.window.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.window.is_empty()
+    }
+
+    /// 追加一期记录
+    pub fn add_record(&mut self, record: &SsqRecord) {
+        let reds = [record.red1, record.red2, record.red3, record.red4, record.red5, record.red6];
+        for &ball in &reds {
+            if let Some(count) = self.red_counts.get_mut(ball as usize) {
+                *count += 1;
+            }
+        }
+        if let Some(count) = self.blue_counts.get_mut(record.blue_ball as usize) {
+            *count += 1;
+        }
+        self.window.push_back((reds, record.blue_ball));
+    }
+
+    /// 移除最早的一期记录
+    pub fn remove_oldest(&mut self) {
+        if let Some((reds, blue)) = self.window.pop_front() {
+            for &ball in &reds {
+                if let Some(count) = self.red_counts.get_mut(ball as usize) {
+                    *count -= 1;
+                }
+            }
+            if let Some(count) = self.blue_counts.get_mut(blue as usize) {
+                *count -= 1;
+            }
+        }
+    }
+
+    /// 当前窗口的红球频率，排序方式与 `Analyzer::analyze_red_frequency` 一致
+    pub fn red_frequencies(&self, algorithm: AlgorithmType) -> Vec<BallFrequency> {
+        self.frequencies(RED_BALL_MIN, RED_BALL_MAX, &self.red_counts, algorithm)
+    }
+
+    /// 当前窗口的蓝球频率，排序方式与 `Analyzer::analyze_blue_frequency` 一致
+    pub fn blue_frequencies(&self, algorithm: AlgorithmType) -> Vec<BallFrequency> {
+        self.frequencies(BLUE_BALL_MIN, BLUE_BALL_MAX, &self.blue_counts, algorithm)
+    }
+
+    fn frequencies(
+        &self,
+        min: u8,
+        max: u8,
+        counts: &[usize],
+        algorithm: AlgorithmType,
+    ) -> Vec<BallFrequency> {
+        let mut frequencies: Vec<BallFrequency> = (min..=max)
+            .map(|num| {
+                let freq = counts[num as usize];
+                BallFrequency {
+                    number: num,
+                    frequency: freq,
+                    weight: Analyzer::calculate_weight(freq, self.window.len(), algorithm),
+                }
+            })
+            .collect();
+
+        frequencies.sort_by(|a, b| b.frequency.cmp(&a.frequency).then(a.number.cmp(&b.number)));
+        frequencies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Distinctness;
+
+    /// 固定种子生成的开奖记录（已排序），期号每年 150 期，从 24001 开始
+    fn sample_records(count: usize) -> Vec<SsqRecord> {
+        let mut rng = StdRng::seed_from_u64(42);
+        let start = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        (0..count)
+            .map(|i| {
+                let mut reds: Vec<u8> = rand::seq::index::sample(&mut rng, 33, 6)
+                    .into_iter()
+                    .map(|idx| idx as u8 + 1)
+                    .collect();
+                reds.sort();
+                let issue = format!("{:02}{:03}", 24 + i / 150, i % 150 + 1);
+                let date = (start + Duration::days(i as i64 * 2)).format("%Y-%m-%d").to_string();
+                SsqRecord::new(issue, date, reds, rng.gen_range(1..=16))
+            })
+            .collect()
+    }
+
+    fn draw(issue: &str, date: &str, reds: [u8; 6], blue: u8) -> SsqRecord {
+        SsqRecord::new(issue.to_string(), date.to_string(), reds.to_vec(), blue)
+    }
+
+    /// 号码 1-10 的权重依次为 1-10
+    fn linear_weights() -> Vec<BallFrequency> {
+        (1..=10)
+            .map(|number| BallFrequency {
+                number,
+                frequency: number as usize,
+                weight: number as f64,
+            })
+            .collect()
+    }
+
+    /// 单选 `trials` 次时权重最高的号码（10）被选中的比例
+    fn top_share(frequencies: &[BallFrequency], temperature: Option<f64>, trials: usize) -> f64 {
+        let mut rng = StdRng::seed_from_u64(7);
+        let hits = (0..trials)
+            .filter(|_| {
+                Analyzer::weighted_random_selection(frequencies, 1, temperature, &mut rng) == [10]
+            })
+            .count();
+        hits as f64 / trials as f64
+    }
+
+    #[test]
+    fn min_gap_is_respected_by_every_prediction() {
+        let records = sample_records(200);
+        let options = PredictionOptions {
+            min_gap: 3,
+            ..Default::default()
+        };
+
+        let predictions = Analyzer::generate_predictions(
+            &records,
+            AlgorithmType::HotStaysHot,
+            AlgorithmType::HotStaysHot,
+            &options,
+        );
+        assert!(!predictions.is_empty());
+        for prediction in &predictions {
+            assert!(
+                prediction.red_balls.windows(2).all(|pair| pair[1] - pair[0] >= 3),
+                "{:?}",
+                prediction.red_balls
+            );
+        }
+    }
+
+    #[test]
+    fn low_temperature_sharpens_and_high_temperature_flattens() {
+        let frequencies = linear_weights();
+        let plain = top_share(&frequencies, None, 4000);
+        let sharp = top_share(&frequencies, Some(1.0), 4000);
+        let flat = top_share(&frequencies, Some(100.0), 4000);
+
+        // 原始权重下约 10/55，T=1 时约 0.63，T=100 时接近均匀的 0.1
+        assert!(sharp > plain + 0.3, "sharp {} plain {}", sharp, plain);
+        assert!(flat < plain, "flat {} plain {}", flat, plain);
+        assert!((flat - 0.1).abs() < 0.03, "flat {}", flat);
+    }
+
+    fn frequency_rows(frequencies: &[BallFrequency]) -> Vec<(u8, usize, f64)> {
+        frequencies.iter().map(|f| (f.number, f.frequency, f.weight)).collect()
+    }
+
+    #[test]
+    fn frequency_tracker_matches_full_recompute() {
+        let records = sample_records(120);
+        let mut tracker = FrequencyTracker::new();
+        let mut start = 0;
+        for (idx, record) in records.iter().enumerate() {
+            tracker.add_record(record);
+            // 窗口在 30 期和 50 期之间来回变化，覆盖连续多次移除
+            let window = if (idx / 40) % 2 == 0 { 50 } else { 30 };
+            while tracker.len() > window {
+                tracker.remove_oldest();
+                start += 1;
+            }
+
+            let current = &records[start..=idx];
+            assert_eq!(tracker.len(), current.len());
+            assert_eq!(
+                frequency_rows(&tracker.red_frequencies(AlgorithmType::HotStaysHot)),
+                frequency_rows(&Analyzer::analyze_red_frequency(current, AlgorithmType::HotStaysHot))
+            );
+            assert_eq!(
+                frequency_rows(&tracker.blue_frequencies(AlgorithmType::ColdBounceBack)),
+                frequency_rows(&Analyzer::analyze_blue_frequency(current, AlgorithmType::ColdBounceBack))
+            );
+        }
+    }
+
+    #[test]
+    fn filter_draws_combines_constraints() {
+        let records = vec![
+            draw("24001", "2024-01-02", [1, 7, 12, 18, 25, 33], 4),
+            draw("24002", "2024-01-04", [2, 7, 13, 19, 26, 31], 5),
+            draw("24003", "2024-01-07", [3, 7, 14, 20, 27, 32], 8),
+            draw("24004", "2024-01-09", [3, 8, 14, 20, 27, 32], 10),
+            draw("24005", "2024-01-11", [7, 9, 10, 11, 13, 15], 2),
+        ];
+        let issues = |filter: &DrawFilter| -> Vec<String> {
+            Analyzer::filter_draws(&records, filter).into_iter().map(|r| r.issue).collect()
+        };
+
+        // 红球 7 且蓝球为偶数
+        let filter = DrawFilter {
+            required_reds: Some(vec![7]),
+            blue_parity: Some(Parity::Even),
+            ..Default::default()
+        };
+        assert_eq!(issues(&filter), ["24001", "24003", "24005"]);
+
+        // 再限定和值和日期
+        let filter = DrawFilter {
+            sum_min: Some(100),
+            sum_max: Some(110),
+            date_from: NaiveDate::from_ymd_opt(2024, 1, 3),
+            ..filter
+        };
+        assert_eq!(issues(&filter), ["24003"]);
+
+        // 指定蓝球号码时与奇偶同时生效，互相矛盾时没有结果
+        let filter = DrawFilter {
+            blue: Some(5),
+            blue_parity: Some(Parity::Even),
+            ..Default::default()
+        };
+        assert!(issues(&filter).is_empty());
+        assert_eq!(issues(&DrawFilter::default()).len(), records.len());
+    }
+
+    #[test]
+    fn weighted_selection_is_distinct_and_biased() {
+        let frequencies = linear_weights();
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut counts = [0usize; 11];
+        for _ in 0..2000 {
+            let selected = Analyzer::weighted_random_selection(&frequencies, 6, None, &mut rng);
+            assert_eq!(selected.len(), 6);
+            assert!(selected.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", selected);
+            for ball in selected {
+                counts[ball as usize] += 1;
+            }
+        }
+        // 权重越高被选中越多，最重的号码远多于最轻的号码
+        assert!(counts[10] > counts[5] && counts[5] > counts[1], "{:?}", counts);
+        assert!(counts[10] > counts[1] * 3, "{:?}", counts);
+
+        // 权重全为 0 时退化为均匀抽取，仍然互不重复
+        let zero: Vec<BallFrequency> = frequencies
+            .iter()
+            .map(|f| BallFrequency { weight: 0.0, ..f.clone() })
+            .collect();
+        let selected = Analyzer::weighted_random_selection(&zero, 10, None, &mut rng);
+        assert_eq!(selected, (1..=10).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn exhausted_candidate_pool_returns_fewer_predictions() {
+        // 每期都是同一组号码，追热时只有这一个组合的权重不为 0
+        let records: Vec<SsqRecord> = (1..=30)
+            .map(|i| draw(&format!("24{:03}", i), "2024-01-02", [1, 2, 3, 4, 5, 6], 1))
+            .collect();
+
+        let predictions = Analyzer::generate_predictions(
+            &records,
+            AlgorithmType::HotStaysHot,
+            AlgorithmType::HotStaysHot,
+            &PredictionOptions::default(),
+        );
+        assert_eq!(predictions.len(), 1);
+        assert_eq!(predictions[0].red_balls, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(predictions[0].blue_ball, 1);
+    }
+
+    #[test]
+    fn shape_penalty_prefers_central_sums_with_equal_weights() {
+        let uniform = |min: u8, max: u8| -> Vec<BallFrequency> {
+            (min..=max)
+                .map(|number| BallFrequency { number, frequency: 1, weight: 1.0 })
+                .collect()
+        };
+        let (reds, blues) = (uniform(1, 33), uniform(1, 16));
+        // 中心和值、跨度由历史数据补全
+        let shape = ShapeGaussian::resolve(&sample_records(300), &ShapePenalty::default()).unwrap();
+        assert!((shape.sum_center - CENTRAL_RED_SUM).abs() < 10.0, "{}", shape.sum_center);
+
+        let central = [4, 11, 17, 21, 26, 30];
+        let extreme = [1, 2, 3, 4, 5, 30];
+        let base = Analyzer::calculate_score(&central, 1, &reds, &blues);
+        assert_eq!(base, Analyzer::calculate_score(&extreme, 1, &reds, &blues));
+        assert!(base * shape.factor(&central) > base * shape.factor(&extreme));
+
+        // 显式指定的中心优先于历史均值
+        let penalty = ShapePenalty {
+            sum_center: Some(45.0),
+            sum_width: Some(10.0),
+            span_center: Some(29.0),
+            span_width: Some(3.0),
+        };
+        let shape = ShapeGaussian::resolve(&[], &penalty).unwrap();
+        assert!(shape.factor(&extreme) > shape.factor(&central));
+    }
+
+    #[test]
+    fn selection_histogram_counts_every_sampled_candidate() {
+        let records = sample_records(100);
+        let options = PredictionOptions {
+            exclude_historical: true,
+            ..Default::default()
+        };
+
+        let report = Analyzer::generate_predictions_with_histogram(
+            &records,
+            AlgorithmType::HotStaysHot,
+            AlgorithmType::HotStaysHot,
+            &options,
+        );
+        // 每次迭代都计数，被排除或去重的候选也包括在内
+        let reds: usize = report.red_sampled.iter().map(|&(_, count)| count).sum();
+        let blues: usize = report.blue_sampled.iter().map(|&(_, count)| count).sum();
+        assert_eq!(reds, 6 * ITERATION_COUNT);
+        assert_eq!(blues, ITERATION_COUNT);
+        assert_eq!(report.predictions.len(), PREDICTION_COUNT);
+    }
+
+    #[test]
+    fn evolve_prediction_never_lowers_the_score() {
+        let records = sample_records(200);
+        let frequencies = Analyzer::analyze_red_frequency(&records, AlgorithmType::HotStaysHot);
+        let blue = Analyzer::analyze_blue_frequency(&records, AlgorithmType::HotStaysHot);
+        // 一注由最热和最冷号码混合而成的号码，最冷的几个应被替换
+        let mut reds: Vec<u8> = frequencies.iter().take(3).map(|f| f.number).collect();
+        reds.extend(frequencies.iter().rev().take(3).map(|f| f.number));
+        reds.sort();
+        let previous = PredictionResult {
+            score: Analyzer::calculate_score(&reds, 1, &frequencies, &blue),
+            red_balls: reds,
+            blue_ball: 1,
+        };
+
+        for change_count in 1..=6 {
+            let evolved =
+                Analyzer::evolve_prediction(&previous, &records, AlgorithmType::HotStaysHot, change_count);
+            assert!(evolved.score >= previous.score, "{} < {}", evolved.score, previous.score);
+            assert_eq!(evolved.blue_ball, 1);
+            let reds = &evolved.red_balls;
+            assert!(reds.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", reds);
+            let kept = reds.iter().filter(|ball| previous.red_balls.contains(ball)).count();
+            assert!(kept >= 6 - change_count);
+        }
+
+        // 已是权重最高的 6 个号码时没有更好的替换，号码保持不变
+        let mut best: Vec<u8> = frequencies.iter().take(6).map(|f| f.number).collect();
+        best.sort();
+        let top = PredictionResult { red_balls: best.clone(), blue_ball: 1, score: 0.0 };
+        assert!(frequencies[5].weight > frequencies[6].weight);
+        let evolved = Analyzer::evolve_prediction(&top, &records, AlgorithmType::HotStaysHot, 2);
+        assert_eq!(evolved.red_balls, best);
+    }
+
+    #[test]
+    fn distribution_entropy_bounds() {
+        let uniform: Vec<BallFrequency> = linear_weights()
+            .into_iter()
+            .map(|f| BallFrequency { weight: 1.0, ..f })
+            .collect();
+        assert!((Analyzer::distribution_entropy(&uniform) - 10f64.log2()).abs() < 1e-9);
+
+        let mut concentrated = uniform.clone();
+        for f in &mut concentrated[1..] {
+            f.weight = 0.0;
+        }
+        assert_eq!(Analyzer::distribution_entropy(&concentrated), 0.0);
+        assert!(Analyzer::distribution_entropy(&linear_weights()) < 10f64.log2());
+    }
+
+    #[test]
+    fn compare_algorithms_covers_every_algorithm() {
+        let profiles = Analyzer::compare_algorithms(&sample_records(200));
+        assert_eq!(profiles.len(), AlgorithmType::ALL.len());
+        assert!(matches!(profiles[2].algorithm, AlgorithmType::Realistic));
+        for profile in &profiles {
+            assert!(profile.red_entropy > 0.0 && profile.red_entropy <= profile.max_red_entropy + 1e-9);
+            assert!(profile.blue_entropy > 0.0 && profile.blue_entropy <= profile.max_blue_entropy + 1e-9);
+        }
+    }
+
+    #[test]
+    fn blue_pool_restricts_every_generated_blue() {
+        let options = PredictionOptions {
+            blue_pool: Some(vec![1, 8, 16]),
+            ..Default::default()
+        };
+        let predictions = Analyzer::generate_predictions(
+            &sample_records(200),
+            AlgorithmType::ColdBounceBack,
+            AlgorithmType::ColdBounceBack,
+            &options,
+        );
+        assert_eq!(predictions.len(), PREDICTION_COUNT);
+        assert!(predictions.iter().all(|p| [1, 8, 16].contains(&p.blue_ball)));
+    }
+
+    #[test]
+    fn zone_ratio_fixes_the_split_of_every_prediction() {
+        for ratio in [[2, 2, 2], [1, 2, 3], [0, 3, 3]] {
+            let options = PredictionOptions {
+                zone_ratio: Some(ratio),
+                ..Default::default()
+            };
+            let predictions = Analyzer::generate_predictions(
+                &sample_records(200),
+                AlgorithmType::HotStaysHot,
+                AlgorithmType::HotStaysHot,
+                &options,
+            );
+            assert_eq!(predictions.len(), PREDICTION_COUNT);
+            for prediction in &predictions {
+                assert_eq!(Analyzer::zone_counts(&prediction.red_balls), ratio);
+            }
+        }
+    }
+
+    #[test]
+    fn equal_frequencies_are_ordered_by_number() {
+        // 27、3、15 各出现两次，其余出现过的号码各一次
+        let records = vec![
+            draw("24001", "2024-01-02", [3, 9, 15, 20, 27, 31], 5),
+            draw("24002", "2024-01-04", [1, 3, 15, 22, 27, 33], 5),
+        ];
+        let frequencies = Analyzer::analyze_red_frequency(&records, AlgorithmType::HotStaysHot);
+        let order: Vec<u8> = frequencies.iter().map(|f| f.number).collect();
+        assert_eq!(order[..9], [3, 15, 27, 1, 9, 20, 22, 31, 33]);
+        assert!(order[9..].windows(2).all(|pair| pair[0] < pair[1]));
+
+        let again = Analyzer::analyze_red_frequency(&records, AlgorithmType::HotStaysHot);
+        assert_eq!(again.iter().map(|f| f.number).collect::<Vec<_>>(), order);
+    }
+
+    #[test]
+    fn synthetic_records_mixed_with_real_ones_are_ignored() {
+        let real = sample_records(60);
+        let mut mixed = real.clone();
+        for (i, mut sample) in sample_records(90)[60..].to_vec().into_iter().enumerate() {
+            sample.synthetic = true;
+            mixed.insert(i * 2, sample);
+        }
+
+        let red_rows = |records: &[SsqRecord]| {
+            frequency_rows(&Analyzer::analyze_red_frequency(records, AlgorithmType::HotStaysHot))
+        };
+        assert_eq!(red_rows(&mixed), red_rows(&real));
+        // 部分结果类型没有实现 PartialEq，按序列化结果比较
+        for is_blue in [false, true] {
+            assert_eq!(
+                serde_json::to_value(Analyzer::omission_stats(&mixed, is_blue)).unwrap(),
+                serde_json::to_value(Analyzer::omission_stats(&real, is_blue)).unwrap()
+            );
+        }
+        assert_eq!(
+            Analyzer::analyze_sum_distribution(&mixed),
+            Analyzer::analyze_sum_distribution(&real)
+        );
+        assert_eq!(
+            Analyzer::analyze_span_distribution(&mixed),
+            Analyzer::analyze_span_distribution(&real)
+        );
+        assert_eq!(
+            Analyzer::red_cooccurrence(&mixed),
+            Analyzer::red_cooccurrence(&real)
+        );
+        assert_eq!(
+            serde_json::to_value(Analyzer::current_streaks(&mixed)).unwrap(),
+            serde_json::to_value(Analyzer::current_streaks(&real)).unwrap()
+        );
+        for number in [1, 17, 33] {
+            assert_eq!(
+                Analyzer::number_trend(&mixed, number, false),
+                Analyzer::number_trend(&real, number, false)
+            );
+        }
+
+        // 全部是示例数据时照常统计
+        let all_synthetic: Vec<SsqRecord> = mixed.iter().filter(|r| r.synthetic).cloned().collect();
+        let counted: usize =
+            Analyzer::analyze_red_frequency(&all_synthetic, AlgorithmType::HotStaysHot)
+                .iter()
+                .map(|f| f.frequency)
+                .sum();
+        assert_eq!(counted, 6 * all_synthetic.len());
+    }
+
+    #[test]
+    fn red_and_blue_algorithms_are_independent() {
+        // 红球只开出过 1-12，蓝球一直是 1
+        let records: Vec<SsqRecord> = (0..20)
+            .map(|i| {
+                let reds = if i % 2 == 0 {
+                    [1, 2, 3, 4, 5, 6]
+                } else {
+                    [7, 8, 9, 10, 11, 12]
+                };
+                draw(&format!("24{:03}", i + 1), "2024-01-02", reds, 1)
+            })
+            .collect();
+
+        let hot_red_cold_blue = Analyzer::generate_predictions(
+            &records,
+            AlgorithmType::HotStaysHot,
+            AlgorithmType::ColdBounceBack,
+            &PredictionOptions::default(),
+        );
+        assert_eq!(hot_red_cold_blue.len(), PREDICTION_COUNT);
+        for prediction in &hot_red_cold_blue {
+            assert!(
+                prediction.red_balls.iter().all(|&ball| ball <= 12),
+                "{:?}",
+                prediction
+            );
+            assert_ne!(prediction.blue_ball, 1);
+        }
+
+        let hot_both = Analyzer::generate_predictions(
+            &records,
+            AlgorithmType::HotStaysHot,
+            AlgorithmType::HotStaysHot,
+            &PredictionOptions::default(),
+        );
+        assert!(hot_both.iter().all(|p| p.blue_ball == 1));
+    }
+
+    #[test]
+    fn exhausted_time_budget_stops_after_the_minimum_iterations() {
+        let options = PredictionOptions {
+            max_duration_ms: Some(0),
+            ..Default::default()
+        };
+        let report = Analyzer::generate_predictions_with_histogram(
+            &sample_records(100),
+            AlgorithmType::HotStaysHot,
+            AlgorithmType::HotStaysHot,
+            &options,
+        );
+        // 预算一开始就用完，仍然先完成最少的迭代次数，再照常排序去重
+        let iterations: usize = report.blue_sampled.iter().map(|&(_, count)| count).sum();
+        assert_eq!(iterations, MIN_ITERATION_COUNT);
+        assert_eq!(report.predictions.len(), PREDICTION_COUNT);
+        assert!(report
+            .predictions
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+    }
+
+    #[test]
+    fn realistic_predictions_follow_historical_sum_band_and_zone_ratio() {
+        let records = sample_records(200);
+        let target = |reds: &[u8]| {
+            let sum: u32 = reds.iter().map(|&ball| ball as u32).sum();
+            (
+                sum / SUM_BAND_WIDTH * SUM_BAND_WIDTH,
+                Analyzer::zone_counts(reds),
+            )
+        };
+        let history: HashSet<(u32, [u8; 3])> =
+            records.iter().map(|r| target(&r.red_balls())).collect();
+        let options = PredictionOptions {
+            entropy: Some("realistic".to_string()),
+            ..Default::default()
+        };
+
+        let predictions = Analyzer::generate_predictions(
+            &records,
+            AlgorithmType::Realistic,
+            AlgorithmType::Realistic,
+            &options,
+        );
+        assert_eq!(predictions.len(), PREDICTION_COUNT);
+        for prediction in &predictions {
+            assert!(
+                history.contains(&target(&prediction.red_balls)),
+                "{:?}",
+                prediction
+            );
+        }
+    }
+
+    #[test]
+    fn date_in_timezone_rolls_over_at_local_midnight() {
+        let at = |time: &str| time.parse::<DateTime<Utc>>().unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        // 北京时间 2024-01-02 00:00 即 UTC 2024-01-01 16:00
+        assert_eq!(
+            Analyzer::date_in_timezone(at("2024-01-01T16:00:00Z"), 8),
+            date(2024, 1, 2)
+        );
+        assert_eq!(
+            Analyzer::date_in_timezone(at("2024-01-01T15:59:59Z"), 8),
+            date(2024, 1, 1)
+        );
+        assert_eq!(
+            Analyzer::date_in_timezone(at("2024-01-01T15:59:59Z"), 0),
+            date(2024, 1, 1)
+        );
+        assert_eq!(
+            Analyzer::date_in_timezone(at("2024-01-01T03:00:00Z"), -5),
+            date(2023, 12, 31)
+        );
+        // 无效偏移按 UTC 计算
+        assert_eq!(
+            Analyzer::date_in_timezone(at("2024-01-01T16:00:00Z"), 30),
+            date(2024, 1, 1)
+        );
+    }
+
+    #[test]
+    fn blend_with_uniform_keeps_the_total_weight() {
+        let total =
+            |frequencies: &[BallFrequency]| frequencies.iter().map(|f| f.weight).sum::<f64>();
+        let original = linear_weights();
+
+        let unchanged = Analyzer::blend_with_uniform(linear_weights(), 1.0);
+        assert_eq!(frequency_rows(&unchanged), frequency_rows(&original));
+
+        let uniform = Analyzer::blend_with_uniform(linear_weights(), 0.0);
+        assert!(uniform.iter().all(|f| (f.weight - 5.5).abs() < 1e-9));
+        assert!((total(&uniform) - total(&original)).abs() < 1e-9);
+
+        let halfway = Analyzer::blend_with_uniform(linear_weights(), 0.5);
+        assert!((halfway[0].weight - 3.25).abs() < 1e-9);
+        assert!((total(&halfway) - total(&original)).abs() < 1e-9);
+        // 频率不受影响
+        assert!(halfway
+            .iter()
+            .zip(&original)
+            .all(|(a, b)| a.frequency == b.frequency));
+    }
+
+    #[test]
+    fn dissimilarity_bounds() {
+        let draws = [draw("24001", "2024-01-02", [1, 5, 12, 18, 25, 33], 7)];
+        let reds = [1, 5, 12, 18, 25, 33];
+        assert_eq!(Analyzer::dissimilarity(&reds, 7, &draws), 0.0);
+        assert_eq!(
+            Analyzer::dissimilarity(&[2, 6, 13, 19, 26, 32], 8, &draws),
+            1.0
+        );
+        assert_eq!(Analyzer::dissimilarity(&reds, 7, &[]), 1.0);
+        // 红球相同、蓝球不同：重合度 6/7
+        assert!((Analyzer::dissimilarity(&reds, 8, &draws) - 1.0 / 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn distinctness_ranks_combinations_unlike_recent_draws_higher() {
+        let records = sample_records(200);
+        let recent = &records[records.len() - 5..];
+        let generate = |distinctness: Option<Distinctness>| {
+            let options = PredictionOptions {
+                distinctness,
+                // 同样的熵得到同样的候选，只有打分不同
+                entropy: Some("distinctness".to_string()),
+                ..Default::default()
+            };
+            Analyzer::generate_predictions(
+                &records,
+                AlgorithmType::HotStaysHot,
+                AlgorithmType::HotStaysHot,
+                &options,
+            )
+        };
+        let mean_dissimilarity = |predictions: &[PredictionResult]| {
+            predictions
+                .iter()
+                .map(|p| Analyzer::dissimilarity(&p.red_balls, p.blue_ball, recent))
+                .sum::<f64>()
+                / predictions.len() as f64
+        };
+
+        let plain = generate(None);
+        let distinct = generate(Some(Distinctness {
+            recent_draws: 5,
+            weight: 0.9,
+        }));
+        assert!(mean_dissimilarity(&distinct) > mean_dissimilarity(&plain));
+    }
+
+    #[test]
+    fn red_only_dedup_yields_distinct_red_sets() {
+        let options = PredictionOptions {
+            dedup_by: DedupMode::RedOnly,
+            entropy: Some("red-only".to_string()),
+            ..Default::default()
+        };
+        let predictions = Analyzer::generate_predictions(
+            &sample_records(200),
+            AlgorithmType::HotStaysHot,
+            AlgorithmType::HotStaysHot,
+            &options,
+        );
+        assert_eq!(predictions.len(), PREDICTION_COUNT);
+        let red_sets: HashSet<&Vec<u8>> = predictions.iter().map(|p| &p.red_balls).collect();
+        assert_eq!(red_sets.len(), PREDICTION_COUNT);
+    }
+
+    #[test]
+    fn historical_bankroll_is_reproducible_from_its_entropy() {
+        let records = sample_records(BANKROLL_WARMUP_DRAWS + 5);
+        let run = |entropy: Option<&str>| {
+            Analyzer::historical_bankroll(&records, AlgorithmType::HotStaysHot, 2, entropy)
+        };
+
+        let first = run(Some("bankroll"));
+        assert_eq!(first.entropy, "bankroll");
+        assert_eq!(first.draws, 5);
+        assert_eq!(first.total_cost, 5 * 2 * TICKET_PRICE);
+        let again = run(Some("bankroll"));
+        assert_eq!(again.balance_history, first.balance_history);
+        assert_eq!(again.total_winnings, first.total_winnings);
+
+        // 未给出熵时随机生成，并可用返回的熵重现
+        let random = run(None);
+        assert!(!random.entropy.is_empty());
+        assert_eq!(
+            run(Some(&random.entropy)).balance_history,
+            random.balance_history
+        );
+    }
+
+    #[test]
+    fn same_entropy_yields_the_same_tickets() {
+        let records = sample_records(200);
+        let fair = |entropy: &str| {
+            Analyzer::fair_predictions(
+                &records,
+                AlgorithmType::HotStaysHot,
+                AlgorithmType::ColdBounceBack,
+                &PredictionOptions::default(),
+                entropy,
+            )
+        };
+        let tickets = |fair: &FairPredictions| -> Vec<(Vec<u8>, u8)> {
+            fair.predictions
+                .iter()
+                .map(|p| (p.red_balls.clone(), p.blue_ball))
+                .collect()
+        };
+
+        let first = fair("2024-06-02 公开的承诺");
+        let again = fair("2024-06-02 公开的承诺");
+        assert_eq!(first.seed, again.seed);
+        assert_eq!(tickets(&first), tickets(&again));
+        assert_eq!(first.predictions.len(), PREDICTION_COUNT);
+
+        let other = fair("2024-06-02 另一个承诺");
+        assert_ne!(other.seed, first.seed);
+        assert_ne!(tickets(&other), tickets(&first));
+        // 公布的种子是熵字符串的 SHA-256
+        assert_eq!(first.seed.len(), 64);
+        assert_eq!(
+            first.seed,
+            Analyzer::entropy_seed("2024-06-02 公开的承诺")
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>()
+        );
+    }
+
+    #[test]
+    fn fairness_deviation_ignores_out_of_range_reds() {
+        let records = [
+            draw("24001", "2024-01-02", [1, 5, 12, 18, 25, 33], 7),
+            // 损坏的 CSV 行：红球 40 超出范围
+            draw("24002", "2024-01-04", [2, 6, 13, 19, 26, 40], 8),
+        ];
+        let report = Analyzer::fairness_deviation(&records);
+        assert_eq!(report.deviations.len(), 33);
+        assert!(report
+            .deviations
+            .iter()
+            .all(|&(number, _)| number <= RED_BALL_MAX));
+        let counted: f64 = report
+            .deviations
+            .iter()
+            .map(|(_, d)| d + report.expected)
+            .sum();
+        assert!((counted - 11.0).abs() < 1e-9);
     }
 }
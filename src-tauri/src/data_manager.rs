@@ -1,23 +1,69 @@
 use anyhow::{Context, Result};
-use chrono::Local;
-use csv::Reader;
+use chrono::{NaiveDate, Utc};
+use csv::{Reader, ReaderBuilder};
+use encoding_rs::{Encoding, GBK};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fs::{self, File};
-use std::path::PathBuf;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 
-use crate::models::SsqRecord;
+use crate::analyzer::{Analyzer, DRAW_UTC_OFFSET_HOURS};
+use crate::fetcher::DataFetcher;
+use crate::models::{
+    DatasetDiff, DatasetFingerprint, IssueGap, IssueSequenceReport, MergePolicy, MergeReport,
+    ReparseReport, SsqRecord,
+};
 
-const MAX_RECORDS: usize = 500;
+pub const MAX_RECORDS: usize = 500;
 const CSV_FILENAME: &str = "ssq_history.csv";
+/// CSV 格式版本：2 表示红球已统一排序（保留出球顺序的导入除外），
+/// 没有版本号注释的旧文件视为 1
+pub const SCHEMA_VERSION: u32 = 2;
+const SCHEMA_VERSION_PREFIX: &str = "# SchemaVersion: ";
+const LAST_UPDATE_PREFIX: &str = "# LastUpdate: ";
+/// 原始响应缓存所在的子目录
+const RAW_CACHE_DIR: &str = "raw_cache";
+/// 最多保留的原始响应个数，超出时删除最早的
+const MAX_CACHED_RESPONSES: usize = 10;
+const CSV_HEADER: &str = "issue,date,red1,red2,red3,red4,red5,red6,blue_ball,note,synthetic";
 
 pub struct DataManager {
     data_dir: PathBuf,
+    max_records: usize,
+    utc_offset_hours: i32,
 }
 
 impl DataManager {
     pub fn new() -> Result<Self> {
         let data_dir = Self::get_data_dir()?;
         fs::create_dir_all(&data_dir)?;
-        Ok(Self { data_dir })
+        Ok(Self {
+            data_dir,
+            max_records: MAX_RECORDS,
+            utc_offset_hours: DRAW_UTC_OFFSET_HOURS,
+        })
+    }
+
+    /// 设置保留的最大期数（默认 `MAX_RECORDS`），手动放入的完整历史可以用更大的值保留
+    pub fn with_max_records(mut self, max_records: usize) -> Self {
+        self.max_records = max_records;
+        self
+    }
+
+    pub fn max_records(&self) -> usize {
+        self.max_records
+    }
+
+    /// 设置判断"今天"所用的时区（相对 UTC 的小时数，默认北京时间）
+    pub fn with_utc_offset_hours(mut self, utc_offset_hours: i32) -> Self {
+        self.utc_offset_hours = utc_offset_hours;
+        self
+    }
+
+    /// 所配置时区的今天，用于记录更新时间
+    pub fn today(&self) -> NaiveDate {
+        Analyzer::date_in_timezone(Utc::now(), self.utc_offset_hours)
     }
 
     fn get_data_dir() -> Result<PathBuf> {
@@ -64,8 +110,66 @@ impl DataManager {
         Ok(None)
     }
 
+    /// 读取 CSV 文件的格式版本（文件开头的注释行），没有版本号时为 1
+    pub fn get_schema_version(&self) -> Result<u32> {
+        let csv_path = self.get_csv_path();
+        if !csv_path.exists() {
+            return Ok(SCHEMA_VERSION);
+        }
+
+        let content = fs::read_to_string(&csv_path)?;
+        let version = content
+            .lines()
+            .take_while(|line| line.starts_with('#'))
+            .find_map(|line| line.strip_prefix(SCHEMA_VERSION_PREFIX))
+            .and_then(|version| version.trim().parse().ok())
+            .unwrap_or(1);
+        Ok(version)
+    }
+
+    /// 一次性迁移旧版 CSV：把所有记录的红球统一排序后写回，并标记为当前格式版本
+    ///
+    /// 旧版文件中示例数据是排序后的，网页抓取的数据却保留了来源顺序，两者混在一起，
+    /// 按位置分析时结果不可靠。迁移后旧文件不再保留出球顺序，需要按位置分析时
+    /// 应另行导入保留出球顺序的数据。已是当前版本时不做任何修改，返回 `None`；
+    /// 否则返回红球顺序被调整的记录数。迁移保留原来的更新时间。
+    pub fn migrate_legacy_csv(&self) -> Result<Option<usize>> {
+        if !self.get_csv_path().exists() || self.get_schema_version()? >= SCHEMA_VERSION {
+            return Ok(None);
+        }
+
+        let mut records = self.load_all_local_data()?;
+        let mut changed = 0;
+        for record in &mut records {
+            let before = record.red_balls();
+            record.sort_red_balls();
+            if record.red_balls() != before {
+                changed += 1;
+            }
+        }
+
+        let last_update = self.get_last_update_time()?;
+        self.write_csv(&records, last_update, SCHEMA_VERSION)?;
+        println!("已迁移旧版 CSV：{} 条记录的红球重新排序", changed);
+        Ok(Some(changed))
+    }
+
     /// 加载本地历史数据
     pub fn load_local_data(&self) -> Result<Vec<SsqRecord>> {
+        let (records, _) = self.load_local_data_with_warning()?;
+        Ok(records)
+    }
+
+    /// 加载本地历史数据，超出保留上限被裁剪时返回提示信息
+    pub fn load_local_data_with_warning(&self) -> Result<(Vec<SsqRecord>, Option<String>)> {
+        let records = self.load_all_local_data()?;
+        Ok(self.retain_with_warning(records))
+    }
+
+    /// 加载本地 CSV 中的全部记录，不按保留上限裁剪
+    ///
+    /// 合并新数据后写回 CSV 时必须基于完整数据，否则超出保留上限的旧记录会被覆盖丢失。
+    pub fn load_all_local_data(&self) -> Result<Vec<SsqRecord>> {
         let csv_path = self.get_csv_path();
         
         if !csv_path.exists() {
@@ -74,92 +178,877 @@ impl DataManager {
 
         let content = fs::read_to_string(&csv_path)?;
         let mut lines = content.lines();
+        let mut records = Vec::new();
         
-        // 跳过第一行（如果是更新时间注释）
-        if let Some(first_line) = lines.next() {
-            if !first_line.starts_with("# LastUpdate:") {
-                // 如果第一行不是注释，需要重新读取整个文件
-                let file = File::open(&csv_path)?;
-                let mut reader = Reader::from_reader(file);
-                let mut records = Vec::new();
-
-                for result in reader.deserialize() {
-                    let record: SsqRecord = result?;
-                    records.push(record);
-                }
+        // 跳过第一行（如果是注释）
+        if lines.next().is_some_and(|first_line| !first_line.starts_with('#')) {
+            // 如果第一行不是注释，需要重新读取整个文件
+            let file = File::open(&csv_path)?;
+            let mut reader = Reader::from_reader(file);
 
-                // 保持最近500期
-                if records.len() > MAX_RECORDS {
-                    records = records.split_off(records.len() - MAX_RECORDS);
-                }
+            for result in reader.deserialize() {
+                let record: SsqRecord = result?;
+                records.push(record);
+            }
+        } else {
+            // 重新构建 CSV 内容（跳过更新时间、版本号等注释行）
+            let csv_content_without_comment = lines
+                .filter(|line| !line.starts_with('#'))
+                .collect::<Vec<&str>>()
+                .join("\n");
+            let mut reader = Reader::from_reader(csv_content_without_comment.as_bytes());
 
-                return Ok(records);
+            for result in reader.deserialize() {
+                let record: SsqRecord = result?;
+                records.push(record);
             }
         }
-        
-        // 重新构建 CSV 内容（跳过注释行）
-        let csv_content_without_comment = lines.collect::<Vec<&str>>().join("\n");
-        let mut reader = Reader::from_reader(csv_content_without_comment.as_bytes());
-        let mut records = Vec::new();
 
-        for result in reader.deserialize() {
-            let record: SsqRecord = result?;
-            records.push(record);
-        }
+        Ok(records)
+    }
 
-        // 保持最近500期
-        if records.len() > MAX_RECORDS {
-            records = records[records.len() - MAX_RECORDS..].to_vec();
-        }
+    /// 只保留最近 `max_records` 期用于分析，裁剪时给出提示；CSV 中的数据不受影响
+    pub fn retain_with_warning(&self, records: Vec<SsqRecord>) -> (Vec<SsqRecord>, Option<String>) {
+        let total = records.len();
+        let (records, dropped) = Self::retain_recent(records, self.max_records);
+        let warning = if dropped.is_empty() {
+            None
+        } else {
+            let message = format!(
+                "本地 CSV 共 {} 条记录，超过保留上限 {} 期，分析时忽略最早的 {} 条（CSV 中仍完整保留）；如需分析完整历史请调大保留期数",
+                total,
+                self.max_records,
+                dropped.len()
+            );
+            println!("⚠️ {}", message);
+            Some(message)
+        };
 
-        Ok(records)
+        (records, warning)
     }
 
-    /// 保存历史数据到本地
+    /// 保存历史数据到本地，更新时间记为今天
+    ///
+    /// 沿用文件原来的格式版本：未迁移的旧文件保存后仍是旧版本，下次加载时照常迁移。
     pub fn save_local_data(&self, records: &[SsqRecord]) -> Result<()> {
-        let csv_path = self.get_csv_path();
+        let schema_version = self.get_schema_version()?;
+        self.write_csv(records, Some(self.today()), schema_version)
+    }
+
+    fn write_csv(
+        &self,
+        records: &[SsqRecord],
+        last_update: Option<chrono::NaiveDate>,
+        schema_version: u32,
+    ) -> Result<()> {
+        Self::write_csv_to(&self.get_csv_path(), records, last_update, schema_version)
+    }
+
+    /// 写入 CSV；`schema_version` 为 1 时与旧文件一样不写版本号注释
+    fn write_csv_to(
+        csv_path: &Path,
+        records: &[SsqRecord],
+        last_update: Option<chrono::NaiveDate>,
+        schema_version: u32,
+    ) -> Result<()> {
         println!("正在保存数据到: {:?}", csv_path);
         
-        // 保存最近500期
-        let start_index = if records.len() > MAX_RECORDS {
-            records.len() - MAX_RECORDS
-        } else {
-            0
-        };
-
-        println!("保存 {} 条记录（从索引 {} 开始）", records.len() - start_index, start_index);
+        // 保留多少期由调用方通过 retain_recent 显式决定，这里原样保存
+        println!("保存 {} 条记录", records.len());
         
         // 使用 String 构建 CSV 内容，然后一次性写入
         let mut csv_content = String::new();
         
-        // 添加更新时间注释（第一行）
-        let today = Local::now().format("%Y-%m-%d");
-        csv_content.push_str(&format!("# LastUpdate: {}\n", today));
+        // 添加更新时间注释（第一行）和格式版本注释
+        if let Some(last_update) = last_update {
+            csv_content.push_str(&Self::last_update_line(last_update));
+        }
+        if schema_version > 1 {
+            csv_content.push_str(&format!("{}{}\n", SCHEMA_VERSION_PREFIX, schema_version));
+        }
         
         // CSV 表头
-        csv_content.push_str("issue,date,red1,red2,red3,red4,red5,red6,blue_ball\n");
+        csv_content.push_str(CSV_HEADER);
+        csv_content.push('\n');
         
-        for (idx, record) in records[start_index..].iter().enumerate() {
+        for (idx, record) in records.iter().enumerate() {
             if idx % 100 == 0 {
                 println!("正在处理第 {} 条记录...", idx);
             }
-            csv_content.push_str(&format!(
-                "{},{},{},{},{},{},{},{},{}\n",
-                record.issue,
-                record.date,
-                record.red1,
-                record.red2,
-                record.red3,
-                record.red4,
-                record.red5,
-                record.red6,
-                record.blue_ball
-            ));
+            csv_content.push_str(&Self::csv_row(record));
         }
 
         println!("CSV内容构建完成，正在写入文件...");
-        std::fs::write(&csv_path, csv_content)?;
+        std::fs::write(csv_path, csv_content)?;
         println!("✅ CSV 文件保存成功");
         Ok(())
     }
+
+    fn last_update_line(date: chrono::NaiveDate) -> String {
+        format!("{}{}\n", LAST_UPDATE_PREFIX, date.format("%Y-%m-%d"))
+    }
+
+    fn csv_row(record: &SsqRecord) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{}\n",
+            record.issue,
+            record.date,
+            record.red1,
+            record.red2,
+            record.red3,
+            record.red4,
+            record.red5,
+            record.red6,
+            record.blue_ball,
+            Self::csv_field(record.note.as_deref().unwrap_or("")),
+            if record.synthetic { "true" } else { "" }
+        )
+    }
+
+    /// 把比本地最新一期更新的记录追加到 CSV 末尾，并原地更新第一行的更新时间
+    ///
+    /// 只有文件是当前格式版本、表头一致、新记录按期号严格递增且都晚于本地最新一期时才追加，
+    /// 结果与完整重写相同；否则回退为合并后完整重写。
+    pub fn append_records(&self, new_records: &[SsqRecord]) -> Result<()> {
+        if self.try_append(new_records)? {
+            println!("✅ 已追加 {} 条记录到 CSV", new_records.len());
+            return Ok(());
+        }
+
+        println!("无法直接追加，改为完整重写 CSV");
+        let (merged_records, _) = Self::merge_records(self.load_all_local_data()?, new_records.to_vec());
+        self.save_local_data(&merged_records)
+    }
+
+    /// 满足追加条件时追加并返回 true，不满足时不修改文件并返回 false
+    fn try_append(&self, new_records: &[SsqRecord]) -> Result<bool> {
+        let csv_path = self.get_csv_path();
+        if !csv_path.exists() || self.get_schema_version()? != SCHEMA_VERSION {
+            return Ok(false);
+        }
+
+        let content = fs::read_to_string(&csv_path)?;
+        let first_line = Self::last_update_line(self.today());
+        let mut lines = content.lines();
+        // 第一行必须是等长的更新时间注释，才能原地覆盖
+        if !lines
+            .next()
+            .is_some_and(|line| line.starts_with(LAST_UPDATE_PREFIX) && line.len() + 1 == first_line.len())
+        {
+            return Ok(false);
+        }
+        if lines.find(|line| !line.starts_with('#')) != Some(CSV_HEADER) || !content.ends_with('\n') {
+            return Ok(false);
+        }
+
+        let existing = self.load_all_local_data()?;
+        let mut latest = existing.iter().map(|r| r.issue_number()).max().flatten();
+        if latest.is_none() && !existing.is_empty() {
+            return Ok(false);
+        }
+        for record in new_records {
+            match record.issue_number() {
+                Some(number) if latest.is_none_or(|latest| number > latest) => latest = Some(number),
+                _ => return Ok(false),
+            }
+        }
+
+        let mut file = fs::OpenOptions::new().write(true).open(&csv_path)?;
+        file.write_all(first_line.as_bytes())?;
+        file.seek(SeekFrom::End(0))?;
+        let rows: String = new_records.iter().map(Self::csv_row).collect();
+        file.write_all(rows.as_bytes())?;
+        Ok(true)
+    }
+
+    /// 含逗号、引号或换行的字段加引号并转义引号
+    fn csv_field(value: &str) -> String {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+
+    /// 从外部 CSV 文件导入历史数据
+    ///
+    /// 支持自定义分隔符（逗号、分号、制表符等）。编码按以下顺序识别：
+    /// 有 BOM 时按 BOM 指示的 UTF-8/UTF-16 解码，否则尝试 UTF-8，失败再按 GBK 解码。
+    /// `preserve_order` 为 true 时红球保留文件中的出球顺序，否则统一排序。
+    pub fn import_csv(path: &Path, delimiter: u8, preserve_order: bool) -> Result<Vec<SsqRecord>> {
+        let bytes = fs::read(path).with_context(|| format!("无法读取文件: {:?}", path))?;
+        let content = Self::decode_text(&bytes);
+
+        // 跳过更新时间、格式版本等注释行
+        let csv_content = content
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .collect::<Vec<&str>>()
+            .join("\n");
+
+        let mut reader = ReaderBuilder::new()
+            .delimiter(delimiter)
+            .trim(csv::Trim::All)
+            .from_reader(csv_content.as_bytes());
+
+        let mut records = Vec::new();
+        for result in reader.deserialize() {
+            let mut record: SsqRecord = result?;
+            if !preserve_order {
+                record.sort_red_balls();
+            }
+            records.push(record);
+        }
+
+        println!("从 {:?} 导入 {} 条记录", path, records.len());
+        Ok(records)
+    }
+
+    /// 合并两个 CSV 文件（如两台电脑上的数据）并写入 `out_path`
+    ///
+    /// 按期号去重后按时间排序；同一期号两边号码或日期不一致时保留 `path_a` 中的记录，
+    /// 并在报告中列为冲突。红球统一排序后再比较，只是顺序不同不算冲突。
+    /// 输出文件不写更新时间，放入数据目录后会在下次启动时检查更新。
+    pub fn merge_files(path_a: &Path, path_b: &Path, out_path: &Path) -> Result<MergeReport> {
+        let records_a = Self::import_csv(path_a, b',', false)?;
+        let records_b = Self::import_csv(path_b, b',', false)?;
+
+        let mut report = MergeReport::default();
+        let mut merged = records_a;
+        for record in records_b {
+            match merged.iter().find(|r| r.issue == record.issue) {
+                None => {
+                    report.added.push(record.issue.clone());
+                    merged.push(record);
+                }
+                Some(existing) if existing.same_draw(&record) => report.duplicates.push(record.issue),
+                Some(_) => report.conflicts.push(record.issue),
+            }
+        }
+
+        Self::sort_chronologically(&mut merged);
+        report.total = merged.len();
+        Self::write_csv_to(out_path, &merged, None, SCHEMA_VERSION)?;
+        println!(
+            "合并完成：共 {} 条，新增 {} 条，重复 {} 条，冲突 {} 条",
+            report.total,
+            report.added.len(),
+            report.duplicates.len(),
+            report.conflicts.len()
+        );
+        Ok(report)
+    }
+
+    /// 将文件内容解码为 UTF-8 文本
+    fn decode_text(bytes: &[u8]) -> String {
+        if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+            let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+            return text.into_owned();
+        }
+
+        match std::str::from_utf8(bytes) {
+            Ok(text) => text.to_string(),
+            Err(_) => {
+                println!("文件不是有效的 UTF-8，按 GBK 解码");
+                let (text, _) = GBK.decode_without_bom_handling(bytes);
+                text.into_owned()
+            }
+        }
+    }
+
+    /// 按期号合并新旧记录（已存在的期号保留本地数据），返回合并结果和新增条数
+    pub fn merge_records(local_records: Vec<SsqRecord>, new_records: Vec<SsqRecord>) -> (Vec<SsqRecord>, usize) {
+        let (merged_records, added_count, _) =
+            Self::merge_records_with(local_records, new_records, MergePolicy::KeepExisting);
+        (merged_records, added_count)
+    }
+
+    /// 按期号合并新旧记录，已存在的期号按 `policy` 处理
+    ///
+    /// 返回合并结果、新增条数和被新记录修正的期号（只有 `PreferFetched` 会修正）。
+    /// 修正时保留本地记录的备注。
+    pub fn merge_records_with(
+        local_records: Vec<SsqRecord>,
+        new_records: Vec<SsqRecord>,
+        policy: MergePolicy,
+    ) -> (Vec<SsqRecord>, usize, Vec<String>) {
+        let mut merged_records = local_records;
+        let mut added_count = 0;
+        let mut corrected = Vec::new();
+        for new_record in new_records {
+            match merged_records.iter_mut().find(|r| r.issue == new_record.issue) {
+                None => {
+                    merged_records.push(new_record);
+                    added_count += 1;
+                }
+                Some(existing) if policy == MergePolicy::PreferFetched && !existing.same_draw(&new_record) => {
+                    println!("期号 {} 的数据已被数据源修正", new_record.issue);
+                    corrected.push(new_record.issue.clone());
+                    let note = existing.note.take();
+                    *existing = SsqRecord { note, ..new_record };
+                }
+                Some(_) => {}
+            }
+        }
+
+        Self::sort_chronologically(&mut merged_records);
+        (merged_records, added_count, corrected)
+    }
+
+    /// 按期号的时间顺序排序：先比较年份再比较当年序号，
+    /// 两位和四位年份的期号可以混排；无法解析的期号排在最前，按字符串排序
+    pub fn sort_chronologically(records: &mut [SsqRecord]) {
+        records.sort_by(|a, b| {
+            (a.issue_number(), &a.issue).cmp(&(b.issue_number(), &b.issue))
+        });
+    }
+
+    /// 检查期号序列（记录需已按时间排序）：缺失的期号区间、顺序错乱和无法解析的期号
+    ///
+    /// 跨年时序号重新从 001 开始，`2024153 → 2025001` 视为连续。
+    pub fn check_issue_sequence(records: &[SsqRecord]) -> IssueSequenceReport {
+        let mut report = IssueSequenceReport::default();
+        let mut previous: Option<(&SsqRecord, _)> = None;
+
+        for record in records {
+            let Some(number) = record.issue_number() else {
+                report.unparsable.push(record.issue.clone());
+                continue;
+            };
+
+            if let Some((prev_record, prev_number)) = previous {
+                if number <= prev_number {
+                    report.out_of_order.push(record.issue.clone());
+                    continue;
+                }
+                let missing = prev_number.missing_before(&number);
+                if missing > 0 {
+                    report.gaps.push(IssueGap {
+                        after: prev_record.issue.clone(),
+                        before: record.issue.clone(),
+                        missing,
+                    });
+                }
+            }
+            previous = Some((record, number));
+        }
+
+        report
+    }
+
+    /// 保留最近的 `max_records` 期（记录需已按时间排序），返回保留的记录和被移除的期号
+    pub fn retain_recent(mut records: Vec<SsqRecord>, max_records: usize) -> (Vec<SsqRecord>, Vec<String>) {
+        if records.len() <= max_records {
+            return (records, Vec::new());
+        }
+
+        let kept = records.split_off(records.len() - max_records);
+        let dropped = records.into_iter().map(|r| r.issue).collect();
+        (kept, dropped)
+    }
+
+    /// 按期号比较新旧数据集，得到新增、缺失和被修正的期号（均按期号排序）
+    pub fn diff(old: &[SsqRecord], new: &[SsqRecord]) -> DatasetDiff {
+        let old_by_issue: HashMap<&str, &SsqRecord> = old.iter().map(|r| (r.issue.as_str(), r)).collect();
+        let new_by_issue: HashMap<&str, &SsqRecord> = new.iter().map(|r| (r.issue.as_str(), r)).collect();
+
+        let mut diff = DatasetDiff::default();
+        for (issue, new_record) in &new_by_issue {
+            match old_by_issue.get(issue) {
+                None => diff.added.push(issue.to_string()),
+                Some(old_record) if !old_record.same_draw(new_record) => diff.changed.push(issue.to_string()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = old_by_issue
+            .keys()
+            .filter(|issue| !new_by_issue.contains_key(*issue))
+            .map(|issue| issue.to_string())
+            .collect();
+
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        diff
+    }
+
+    /// 数据集指纹：先按期号排序，再对每期的期号、排序后的红球和蓝球计算 SHA-256，
+    /// 因此与传入记录的顺序和红球的出球顺序无关；备注等附加信息不参与计算
+    pub fn dataset_fingerprint(records: &[SsqRecord]) -> DatasetFingerprint {
+        let mut sorted = records.to_vec();
+        Self::sort_chronologically(&mut sorted);
+
+        let mut hasher = Sha256::new();
+        for record in &sorted {
+            let reds: Vec<String> = record.sorted_red_balls().iter().map(|b| b.to_string()).collect();
+            hasher.update(format!("{},{},{}\n", record.issue, reds.join(","), record.blue_ball));
+        }
+        let hash = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        DatasetFingerprint {
+            hash,
+            record_count: sorted.len(),
+            first_date: sorted.first().map(|r| r.date.clone()),
+            last_date: sorted.last().map(|r| r.date.clone()),
+        }
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// 把原始响应保存为 `raw_cache/<UTC 时间戳>.<extension>`，只保留最近 `MAX_CACHED_RESPONSES` 个
+    pub fn cache_raw_response(&self, body: &str, extension: &str) -> Result<PathBuf> {
+        let cache_dir = self.data_dir.join(RAW_CACHE_DIR);
+        fs::create_dir_all(&cache_dir)?;
+        let path = cache_dir.join(format!("{}.{}", Utc::now().format("%Y%m%d-%H%M%S%3f"), extension));
+        fs::write(&path, body).with_context(|| format!("无法写入原始响应: {:?}", path))?;
+
+        let cached = self.cached_responses()?;
+        for old in cached.iter().skip(MAX_CACHED_RESPONSES) {
+            fs::remove_file(old)?;
+        }
+        Ok(path)
+    }
+
+    /// 已保存的原始响应，最新的在前（文件名即时间戳）
+    pub fn cached_responses(&self) -> Result<Vec<PathBuf>> {
+        let cache_dir = self.data_dir.join(RAW_CACHE_DIR);
+        if !cache_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut files: Vec<PathBuf> = fs::read_dir(&cache_dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .collect();
+        files.sort();
+        files.reverse();
+        Ok(files)
+    }
+
+    /// 不联网，重新解析已保存的原始响应；`file` 为空时解析最新的一个
+    pub fn reparse_cached(&self, file: Option<&str>, max_count: usize) -> Result<ReparseReport> {
+        let cached = self.cached_responses()?;
+        let path = match file {
+            Some(name) => cached
+                .into_iter()
+                .find(|path| path.file_name().is_some_and(|n| n == name))
+                .with_context(|| format!("没有找到缓存的响应: {}", name))?,
+            None => cached.into_iter().next().context("没有缓存的原始响应")?,
+        };
+
+        let body = fs::read_to_string(&path)?;
+        let (records, diagnostics) = if path.extension().is_some_and(|ext| ext == "json") {
+            (DataFetcher::parse_json(&body)?, None)
+        } else {
+            let (records, diagnostics) = DataFetcher::parse_html(&body, max_count, self.today());
+            (records, Some(diagnostics))
+        };
+
+        Ok(ReparseReport {
+            file: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            records,
+            diagnostics,
+        })
+    }
+
+    /// 检查数据目录是否可写（写入并删除一个临时文件）
+    pub fn check_writable(&self) -> Result<()> {
+        let probe_path = self.data_dir.join(".write_probe");
+        fs::write(&probe_path, b"ok").with_context(|| format!("无法写入数据目录: {:?}", self.data_dir))?;
+        fs::remove_file(&probe_path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每个测试独立的临时目录
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ssq-predictor-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 数据目录指向 `dir` 的管理器，其余设置为默认值
+    fn manager_in(dir: &Path) -> DataManager {
+        DataManager {
+            data_dir: dir.to_path_buf(),
+            max_records: MAX_RECORDS,
+            utc_offset_hours: DRAW_UTC_OFFSET_HOURS,
+        }
+    }
+
+    /// `count` 期按时间排序的记录，期号从 20001 开始，每年 150 期
+    fn records(count: usize) -> Vec<SsqRecord> {
+        (0..count)
+            .map(|i| {
+                let issue = format!("{:02}{:03}", 20 + i / 150, i % 150 + 1);
+                let first = (i % 28) as u8 + 1;
+                let reds = vec![first, first + 1, first + 2, first + 3, first + 4, first + 5];
+                SsqRecord::new(issue, "2020-01-02".to_string(), reds, (i % 16) as u8 + 1)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn import_csv_decodes_gbk_with_custom_delimiter() {
+        let dir = temp_dir("import-gbk");
+        let path = dir.join("gbk.csv");
+        let text = "issue;date;red1;red2;red3;red4;red5;red6;blue_ball;note\n\
+                    24001;2024-01-02;18;05;33;01;25;12;07;春节特别开奖\n";
+        let (bytes, _, _) = GBK.encode(text);
+        fs::write(&path, &bytes).unwrap();
+
+        let records = DataManager::import_csv(&path, b';', false).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].red_balls(), [1, 5, 12, 18, 25, 33]);
+        assert_eq!(records[0].blue_ball, 7);
+        assert_eq!(records[0].note.as_deref(), Some("春节特别开奖"));
+    }
+
+    #[test]
+    fn import_csv_honours_byte_order_marks() {
+        let dir = temp_dir("import-bom");
+        let text = "issue\tdate\tred1\tred2\tred3\tred4\tred5\tred6\tblue_ball\n\
+                    24001\t2024-01-02\t1\t5\t12\t18\t25\t33\t7\n";
+
+        let utf8_path = dir.join("utf8.csv");
+        let mut utf8 = vec![0xEF, 0xBB, 0xBF];
+        utf8.extend_from_slice(text.as_bytes());
+        fs::write(&utf8_path, utf8).unwrap();
+
+        let utf16_path = dir.join("utf16.csv");
+        let mut utf16 = vec![0xFF, 0xFE];
+        utf16.extend(text.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        fs::write(&utf16_path, utf16).unwrap();
+
+        for path in [utf8_path, utf16_path] {
+            let records = DataManager::import_csv(&path, b'\t', false).unwrap();
+            assert_eq!(records.len(), 1, "{:?}", path);
+            assert_eq!(records[0].issue, "24001");
+        }
+    }
+
+    #[test]
+    fn loading_an_oversized_csv_warns_and_keeps_the_file_intact() {
+        let dir = temp_dir("oversized");
+        let manager = manager_in(&dir);
+        manager.save_local_data(&records(600)).unwrap();
+
+        let (loaded, warning) = manager.load_local_data_with_warning().unwrap();
+        assert_eq!(loaded.len(), MAX_RECORDS);
+        assert_eq!(loaded.last().unwrap().issue, "23150");
+        let warning = warning.expect("裁剪时应给出提示");
+        assert!(warning.contains("600") && warning.contains("100"), "{}", warning);
+        assert_eq!(manager.load_all_local_data().unwrap().len(), 600);
+
+        // 调大保留期数后完整加载，没有提示
+        let manager = manager.with_max_records(1000);
+        let (loaded, warning) = manager.load_local_data_with_warning().unwrap();
+        assert_eq!(loaded.len(), 600);
+        assert!(warning.is_none());
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_changed_issues() {
+        let old = records(5);
+        let mut new = old[1..].to_vec();
+        new[1].blue_ball = 16;
+        new[2].note = Some("只改备注".to_string());
+        let mut added = records(6).pop().unwrap();
+        added.issue = "20100".to_string();
+        new.push(added);
+
+        let diff = DataManager::diff(&old, &new);
+        assert_eq!(diff.added, ["20100"]);
+        assert_eq!(diff.removed, ["20001"]);
+        assert_eq!(diff.changed, ["20003"]);
+
+        assert!(DataManager::diff(&old, &old).added.is_empty());
+        assert!(DataManager::diff(&old, &old).changed.is_empty());
+    }
+
+    #[test]
+    fn diff_of_equally_trimmed_sets_ignores_the_retention_window() {
+        let dir = temp_dir("diff-trimmed");
+        let manager = manager_in(&dir).with_max_records(100);
+        manager.save_local_data(&records(300)).unwrap();
+
+        let (in_memory, _) = DataManager::retain_recent(records(300), 100);
+        let local = manager.load_local_data().unwrap();
+        let diff = DataManager::diff(&in_memory, &local);
+        assert!(diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty());
+    }
+
+    fn issue_records(issues: &[&str]) -> Vec<SsqRecord> {
+        issues
+            .iter()
+            .map(|issue| {
+                SsqRecord::new(issue.to_string(), "2024-12-31".to_string(), vec![1, 2, 3, 4, 5, 6], 1)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sorting_and_gap_detection_span_a_year_boundary() {
+        let mut records = issue_records(&["2025002", "2024153", "24150", "2025001", "2024151"]);
+        DataManager::sort_chronologically(&mut records);
+        let issues: Vec<&str> = records.iter().map(|r| r.issue.as_str()).collect();
+        assert_eq!(issues, ["24150", "2024151", "2024153", "2025001", "2025002"]);
+
+        // 2024153 → 2025001 是连续的，只缺 2024152
+        let report = DataManager::check_issue_sequence(&records);
+        assert_eq!(report.gaps.len(), 1);
+        assert_eq!(report.gaps[0].after, "2024151");
+        assert_eq!(report.gaps[0].before, "2024153");
+        assert_eq!(report.gaps[0].missing, 1);
+        assert!(report.out_of_order.is_empty() && report.unparsable.is_empty());
+
+        // 新一年不是从第 1 期开始时，缺失的是新一年之前的期数
+        let report = DataManager::check_issue_sequence(&issue_records(&["2024153", "2025004"]));
+        assert_eq!(report.gaps[0].missing, 3);
+    }
+
+    #[test]
+    fn issue_sequence_flags_out_of_order_and_unparsable_issues() {
+        let records = issue_records(&["2025001", "2024153", "abc", "2025002"]);
+        let report = DataManager::check_issue_sequence(&records);
+        assert_eq!(report.out_of_order, ["2024153"]);
+        assert_eq!(report.unparsable, ["abc"]);
+        assert!(report.gaps.is_empty());
+    }
+
+    #[test]
+    fn import_keeps_draw_order_only_when_asked() {
+        let dir = temp_dir("import-order");
+        let path = dir.join("as_drawn.csv");
+        let content = "issue,date,red1,red2,red3,red4,red5,red6,blue_ball\n\
+                       24001,2024-01-02,18,5,33,1,25,12,7\n";
+        fs::write(&path, content).unwrap();
+
+        let preserved = DataManager::import_csv(&path, b',', true).unwrap();
+        assert_eq!(preserved[0].red_balls(), [18, 5, 33, 1, 25, 12]);
+        assert_eq!(preserved[0].sorted_red_balls(), [1, 5, 12, 18, 25, 33]);
+
+        let sorted = DataManager::import_csv(&path, b',', false).unwrap();
+        assert_eq!(sorted[0].red_balls(), [1, 5, 12, 18, 25, 33]);
+    }
+
+    #[test]
+    fn merging_a_short_fetch_keeps_older_local_records() {
+        let dir = temp_dir("merge-window");
+        let manager = manager_in(&dir);
+        manager.save_local_data(&records(800)).unwrap();
+
+        // 抓取只返回最近 500 期，其中最后一期是新开奖
+        let fetched = records(801).split_off(301);
+        let local = manager.load_all_local_data().unwrap();
+        let (merged, added, corrected) =
+            DataManager::merge_records_with(local, fetched.clone(), MergePolicy::KeepExisting);
+        assert_eq!((merged.len(), added), (801, 1));
+        assert!(corrected.is_empty());
+
+        // 追加和完整重写两种保存方式都不丢失抓取窗口之外的旧记录
+        manager.append_records(&fetched[fetched.len() - 1..]).unwrap();
+        assert_eq!(manager.load_all_local_data().unwrap().len(), 801);
+        manager.save_local_data(&merged).unwrap();
+        let saved = manager.load_all_local_data().unwrap();
+        assert_eq!(saved.len(), 801);
+        assert_eq!(saved[0].issue, "20001");
+
+        // 只有分析用的数据按保留期数裁剪
+        assert_eq!(manager.load_local_data().unwrap().len(), MAX_RECORDS);
+    }
+
+    #[test]
+    fn written_csv_can_be_imported_again() {
+        let dir = temp_dir("round-trip");
+        let manager = manager_in(&dir);
+        let mut saved = records(20);
+        saved[3].note = Some("含逗号, 和\"引号\"的备注".to_string());
+        saved[4].synthetic = true;
+        manager.save_local_data(&saved).unwrap();
+
+        let imported = DataManager::import_csv(&manager.get_csv_path(), b',', true).unwrap();
+        assert_eq!(imported, saved);
+    }
+
+    /// 旧版 CSV：没有版本号注释，红球保留来源顺序
+    fn write_legacy_csv(manager: &DataManager) {
+        let content = "# LastUpdate: 2024-01-05\n\
+                       issue,date,red1,red2,red3,red4,red5,red6,blue_ball\n\
+                       24001,2024-01-02,18,5,33,1,25,12,7\n\
+                       24002,2024-01-04,2,6,13,19,26,31,8\n";
+        fs::write(manager.get_csv_path(), content).unwrap();
+    }
+
+    #[test]
+    fn migration_sorts_reds_and_marks_the_schema_version() {
+        let dir = temp_dir("migrate");
+        let manager = manager_in(&dir);
+        write_legacy_csv(&manager);
+        assert_eq!(manager.get_schema_version().unwrap(), 1);
+
+        assert_eq!(manager.migrate_legacy_csv().unwrap(), Some(1));
+        assert_eq!(manager.get_schema_version().unwrap(), SCHEMA_VERSION);
+        let migrated = manager.load_all_local_data().unwrap();
+        assert!(migrated.iter().all(|r| r.red_balls() == r.sorted_red_balls()));
+        assert_eq!(migrated[0].red_balls(), [1, 5, 12, 18, 25, 33]);
+        // 迁移保留原来的更新时间，再次迁移不做任何修改
+        assert_eq!(manager.get_last_update_time().unwrap(), NaiveDate::from_ymd_opt(2024, 1, 5));
+        assert_eq!(manager.migrate_legacy_csv().unwrap(), None);
+    }
+
+    #[test]
+    fn saving_without_migration_keeps_the_legacy_version() {
+        let dir = temp_dir("skip-migrate");
+        let manager = manager_in(&dir);
+        write_legacy_csv(&manager);
+
+        // 例如设置备注：读出全部记录后原样写回
+        let mut local = manager.load_all_local_data().unwrap();
+        local[1].note = Some("测试".to_string());
+        manager.save_local_data(&local).unwrap();
+        assert_eq!(manager.get_schema_version().unwrap(), 1);
+
+        // 之后仍会迁移
+        assert_eq!(manager.migrate_legacy_csv().unwrap(), Some(1));
+        assert_eq!(manager.get_schema_version().unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn merge_files_reports_added_duplicates_and_conflicts() {
+        let dir = temp_dir("merge-files");
+        let manager_a = manager_in(&dir.join("a"));
+        let manager_b = manager_in(&dir.join("b"));
+        fs::create_dir_all(&manager_a.data_dir).unwrap();
+        fs::create_dir_all(&manager_b.data_dir).unwrap();
+        let mut a = records(4);
+        let mut b = records(6)[1..].to_vec();
+        // 20002：同一期红球顺序不同（如保留出球顺序导入），按重复处理
+        let mut reds = b[0].red_balls();
+        reds.reverse();
+        b[0] = SsqRecord::new(b[0].issue.clone(), b[0].date.clone(), reds.to_vec(), b[0].blue_ball);
+        // 20003：蓝球不同，保留 a 的记录
+        b[1].blue_ball = a[2].blue_ball % 16 + 1;
+        a[0].note = Some("仅 a".to_string());
+        manager_a.save_local_data(&a).unwrap();
+        manager_b.save_local_data(&b).unwrap();
+
+        let out = dir.join("merged.csv");
+        let report =
+            DataManager::merge_files(&manager_a.get_csv_path(), &manager_b.get_csv_path(), &out)
+                .unwrap();
+        assert_eq!(report.added, ["20005", "20006"]);
+        assert_eq!(report.duplicates, ["20002", "20004"]);
+        assert_eq!(report.conflicts, ["20003"]);
+        assert_eq!(report.total, 6);
+
+        let merged = DataManager::import_csv(&out, b',', true).unwrap();
+        let issues: Vec<&str> = merged.iter().map(|r| r.issue.as_str()).collect();
+        assert_eq!(issues, ["20001", "20002", "20003", "20004", "20005", "20006"]);
+        assert_eq!(merged[0].note.as_deref(), Some("仅 a"));
+        assert_eq!(merged[2].blue_ball, a[2].blue_ball);
+        assert!(merged.iter().all(|r| r.red_balls() == r.sorted_red_balls()));
+    }
+
+    #[test]
+    fn fetched_correction_applies_only_when_preferring_fetched() {
+        let mut stored = records(3);
+        stored[1].note = Some("数据存疑".to_string());
+        let mut corrected = stored[1].clone();
+        corrected.note = None;
+        corrected.blue_ball = stored[1].blue_ball % 16 + 1;
+        // 同一期只是红球顺序不同，不算修正
+        let mut reds = stored[2].red_balls();
+        reds.reverse();
+        let reordered = SsqRecord::new(
+            stored[2].issue.clone(),
+            stored[2].date.clone(),
+            reds.to_vec(),
+            stored[2].blue_ball,
+        );
+        let fetched = vec![corrected.clone(), reordered];
+
+        let (kept, added, changed) = DataManager::merge_records_with(
+            stored.clone(),
+            fetched.clone(),
+            MergePolicy::KeepExisting,
+        );
+        assert_eq!(kept, stored);
+        assert_eq!(added, 0);
+        assert!(changed.is_empty());
+
+        let (merged, added, changed) =
+            DataManager::merge_records_with(stored.clone(), fetched, MergePolicy::PreferFetched);
+        assert_eq!(added, 0);
+        assert_eq!(changed, [stored[1].issue.clone()]);
+        assert_eq!(merged[1].blue_ball, corrected.blue_ball);
+        // 修正后保留本地备注
+        assert_eq!(merged[1].note.as_deref(), Some("数据存疑"));
+        assert_eq!(merged[2], stored[2]);
+    }
+
+    /// 以较早的更新时间写入当前格式的 CSV，模拟上次启动时保存的文件
+    fn write_stale_csv(manager: &DataManager, saved: &[SsqRecord]) {
+        let last_update = NaiveDate::from_ymd_opt(2020, 1, 1);
+        DataManager::write_csv_to(&manager.get_csv_path(), saved, last_update, SCHEMA_VERSION)
+            .unwrap();
+    }
+
+    #[test]
+    fn append_produces_the_same_file_as_a_full_rewrite() {
+        let dir = temp_dir("append-bytes");
+        let appended = manager_in(&dir.join("appended"));
+        let rewritten = manager_in(&dir.join("rewritten"));
+        fs::create_dir_all(&appended.data_dir).unwrap();
+        fs::create_dir_all(&rewritten.data_dir).unwrap();
+        let mut all = records(21);
+        all[20].note = Some("含逗号, 的备注".to_string());
+
+        write_stale_csv(&appended, &all[..20]);
+        assert!(appended.try_append(&all[20..]).unwrap());
+        rewritten.save_local_data(&all).unwrap();
+
+        assert_eq!(
+            fs::read(appended.get_csv_path()).unwrap(),
+            fs::read(rewritten.get_csv_path()).unwrap()
+        );
+    }
+
+    #[test]
+    fn duplicate_or_older_issues_fall_back_to_a_full_rewrite() {
+        let dir = temp_dir("append-fallback");
+        let manager = manager_in(&dir.join("appended"));
+        let rewritten = manager_in(&dir.join("rewritten"));
+        fs::create_dir_all(&manager.data_dir).unwrap();
+        fs::create_dir_all(&rewritten.data_dir).unwrap();
+        let all = records(21);
+        write_stale_csv(&manager, &all[..20]);
+        let before = fs::read(manager.get_csv_path()).unwrap();
+
+        // 已存在的期号、早于本地最新一期的期号都不能直接追加，文件保持不变
+        assert!(!manager.try_append(&all[19..]).unwrap());
+        assert!(!manager
+            .try_append(&[all[20].clone(), all[5].clone()])
+            .unwrap());
+        assert_eq!(fs::read(manager.get_csv_path()).unwrap(), before);
+
+        // 回退为合并后完整重写，结果与直接保存完整数据相同
+        manager.append_records(&all[19..]).unwrap();
+        rewritten.save_local_data(&all).unwrap();
+        assert_eq!(
+            fs::read(manager.get_csv_path()).unwrap(),
+            fs::read(rewritten.get_csv_path()).unwrap()
+        );
+    }
 }
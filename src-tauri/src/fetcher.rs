@@ -1,55 +1,343 @@
-use anyhow::{anyhow, Result};
+use anyhow::{bail, Result};
+use chrono::Datelike;
 use scraper::{Html, Selector};
+use serde::Deserialize;
+use std::path::PathBuf;
 
-use crate::models::SsqRecord;
+use crate::analyzer::{Analyzer, DRAW_UTC_OFFSET_HOURS};
+use crate::data_manager::DataManager;
+use crate::models::{DataSource, ParseDiagnostics, RowFailure, SelectorAttempt, SsqRecord};
+
+/// 默认请求超时（秒）
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// 网络请求参数
+#[derive(Debug, Clone, Copy)]
+pub struct FetchConfig {
+    /// 单次请求超时（秒）
+    pub timeout_secs: u64,
+    /// 请求失败后的重试次数，0 表示不重试
+    pub max_retries: u32,
+    /// 调试用：解析前把原始响应保存到数据目录，便于之后离线重新解析
+    pub cache_raw: bool,
+    /// 判断"今天"所用时区的 UTC 偏移（小时），如年份范围、缺失日期的默认值
+    pub utc_offset_hours: i32,
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            max_retries: 0,
+            cache_raw: false,
+            utc_offset_hours: DRAW_UTC_OFFSET_HOURS,
+        }
+    }
+}
+
+impl FetchConfig {
+    /// 所设时区的今天
+    pub fn today(&self) -> chrono::NaiveDate {
+        Analyzer::date_in_timezone(chrono::Utc::now(), self.utc_offset_hours)
+    }
+}
+
+/// 双色球首次开奖的年份
+const FIRST_DRAW_YEAR: i32 = 2003;
+/// 每年开奖期数的上限（每年约 150 余期）
+const MAX_DRAWS_PER_YEAR: usize = 200;
+
+/// 中国福彩网开奖公告 JSON 接口的响应
+#[derive(Debug, Deserialize)]
+struct CwlResponse {
+    /// 0 表示查询成功
+    state: i32,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    result: Vec<CwlDraw>,
+}
+
+/// JSON 接口中的单期开奖，如 `{"code": "2024001", "date": "2024-01-02(二)", "red": "01,05,...", "blue": "07"}`
+#[derive(Debug, Deserialize)]
+struct CwlDraw {
+    code: String,
+    date: String,
+    red: String,
+    blue: String,
+}
+
+/// 历史开奖数据的来源
+///
+/// 新增数据源只需实现该 trait，再加入 `DataFetcher::default_sources` 或传给 `DataFetcher::fetch_from`。
+pub trait RecordSource {
+    /// 来源名称，用于日志
+    fn name(&self) -> &str;
+
+    /// 获取最近 `max_count` 期数据（按期号升序）
+    fn fetch(&self, max_count: usize) -> Result<Vec<SsqRecord>>;
+}
+
+/// 福彩网开奖公告 JSON 接口
+pub struct CwlJsonSource {
+    pub config: FetchConfig,
+}
+
+impl RecordSource for CwlJsonSource {
+    fn name(&self) -> &str {
+        "福彩网 JSON 接口"
+    }
+
+    fn fetch(&self, max_count: usize) -> Result<Vec<SsqRecord>> {
+        DataFetcher::fetch_history_json(max_count, &self.config)
+    }
+}
+
+/// datachart.500.com 历史数据网页
+pub struct Html500Source {
+    pub config: FetchConfig,
+}
+
+impl RecordSource for Html500Source {
+    fn name(&self) -> &str {
+        "500.com 网页"
+    }
+
+    fn fetch(&self, max_count: usize) -> Result<Vec<SsqRecord>> {
+        let html = DataFetcher::fetch_html(max_count, &self.config)?;
+        let (records, diagnostics) = DataFetcher::parse_html(&html, max_count, self.config.today());
+        if records.is_empty() {
+            bail!("解析网页失败: {}", diagnostics.summary());
+        }
+        Ok(records)
+    }
+}
+
+/// 外部 CSV 文件
+pub struct CsvFileSource {
+    pub path: PathBuf,
+    pub delimiter: u8,
+    /// 保留文件中的红球出球顺序
+    pub preserve_order: bool,
+}
+
+impl RecordSource for CsvFileSource {
+    fn name(&self) -> &str {
+        "CSV 文件"
+    }
+
+    fn fetch(&self, max_count: usize) -> Result<Vec<SsqRecord>> {
+        let mut records = DataManager::import_csv(&self.path, self.delimiter, self.preserve_order)?;
+        DataManager::sort_chronologically(&mut records);
+        let (records, _) = DataManager::retain_recent(records, max_count);
+        Ok(records)
+    }
+}
 
 pub struct DataFetcher;
 
 impl DataFetcher {
-    /// 从 datachart.500.com 获取双色球历史数据
-    pub fn fetch_history(max_count: usize) -> Result<Vec<SsqRecord>> {
+    /// 获取双色球历史数据
+    ///
+    /// 优先使用福彩网的 JSON 接口，失败时改为抓取 datachart.500.com 的网页；
+    /// 都失败时回退到示例数据，并通过 `DataSource::Sample` 标明。
+    pub fn fetch_history(max_count: usize, config: &FetchConfig) -> Result<(Vec<SsqRecord>, DataSource)> {
+        Self::fetch_from(&Self::default_sources(config), max_count, config.today())
+    }
+
+    /// 默认的网络数据源，按优先级排列
+    pub fn default_sources(config: &FetchConfig) -> Vec<Box<dyn RecordSource>> {
+        vec![
+            Box::new(CwlJsonSource { config: *config }),
+            Box::new(Html500Source { config: *config }),
+        ]
+    }
+
+    /// 按顺序尝试每个数据源，第一个返回数据的来源胜出；全部失败时回退到截至 `today` 的示例数据
+    pub fn fetch_from(
+        sources: &[Box<dyn RecordSource>],
+        max_count: usize,
+        today: chrono::NaiveDate,
+    ) -> Result<(Vec<SsqRecord>, DataSource)> {
+        for source in sources {
+            match source.fetch(max_count) {
+                Ok(records) if !records.is_empty() => {
+                    println!("成功从 {} 获取 {} 条记录", source.name(), records.len());
+                    return Ok((records, DataSource::Network));
+                }
+                Ok(_) => println!("{} 没有返回数据，尝试下一个数据源", source.name()),
+                Err(e) => println!("{} 获取失败: {}, 尝试下一个数据源", source.name(), e),
+            }
+        }
+        
+        // 如果网络获取失败，返回示例数据
+        println!("提示：使用示例数据进行演示");
+        Ok((Self::generate_sample_data(max_count, today)?, DataSource::Sample))
+    }
+
+    /// 获取网页并返回解析诊断信息，不回退到示例数据，用于排查抓取问题
+    pub fn fetch_diagnostics(max_count: usize, config: &FetchConfig) -> Result<ParseDiagnostics> {
+        let html = Self::fetch_html(max_count, config)?;
+        let (_, diagnostics) = Self::parse_html(&html, max_count, config.today());
+        Ok(diagnostics)
+    }
+
+    /// 快速探测数据源是否可以连通，只发送 HEAD 请求，不下载数据
+    pub fn probe_source(timeout_secs: u64) -> Result<()> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+            .timeout(std::time::Duration::from_secs(timeout_secs))
+            .build()?;
+
+        client
+            .head("https://datachart.500.com/ssq/history/newinc/history.php")
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// 只获取指定年份的开奖数据
+    ///
+    /// 期号前两位是年份（如 24001 为 2024 年第 1 期），
+    /// 因此用 start/end 期号区间只请求该年的数据。
+    pub fn fetch_year(year: i32, config: &FetchConfig) -> Result<Vec<SsqRecord>> {
+        let today = config.today();
+        let current_year = today.year();
+        if !(FIRST_DRAW_YEAR..=current_year).contains(&year) {
+            bail!("年份 {} 超出范围，应在 {}-{} 之间", year, FIRST_DRAW_YEAR, current_year);
+        }
+
+        let prefix = format!("{:02}", year % 100);
+        let url = format!(
+            "https://datachart.500.com/ssq/history/newinc/history.php?start={}001&end={}999",
+            prefix, prefix
+        );
+        let html = Self::fetch_url(&url, config)?;
+        let (records, diagnostics) = Self::parse_html(&html, MAX_DRAWS_PER_YEAR, today);
+
+        // 数据源可能忽略区间参数，只保留该年的期号
+        let records: Vec<SsqRecord> = records
+            .into_iter()
+            .filter(|record| record.issue.len() == 5 && record.issue.starts_with(&prefix))
+            .collect();
+        if records.is_empty() {
+            bail!("{} 年没有开奖数据: {}", year, diagnostics.summary());
+        }
+
+        println!("成功获取 {} 年 {} 条记录", year, records.len());
+        Ok(records)
+    }
+
+    fn fetch_html(max_count: usize, config: &FetchConfig) -> Result<String> {
         let url = format!(
             "https://datachart.500.com/ssq/history/newinc/history.php?limit={}",
             max_count.min(500)
         );
-        
+        let html = Self::fetch_url(&url, config)?;
+        Self::cache_raw(&html, "html", config);
+        Ok(html)
+    }
+
+    /// 开启 `cache_raw` 时保存原始响应，保存失败只记录日志，不影响本次获取
+    fn cache_raw(body: &str, extension: &str, config: &FetchConfig) {
+        if !config.cache_raw {
+            return;
+        }
+        match DataManager::new().and_then(|manager| manager.cache_raw_response(body, extension)) {
+            Ok(path) => println!("原始响应已保存到 {:?}", path),
+            Err(e) => println!("保存原始响应失败: {}", e),
+        }
+    }
+
+    /// 按 `config` 的超时设置请求，失败后最多重试 `max_retries` 次
+    fn fetch_url(url: &str, config: &FetchConfig) -> Result<String> {
         println!("正在从 {} 获取数据...", url);
         
         // 设置请求头，模拟浏览器
         let client = reqwest::blocking::Client::builder()
             .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-            .timeout(std::time::Duration::from_secs(60))
+            .timeout(std::time::Duration::from_secs(config.timeout_secs))
             .build()?;
         
-        match client.get(&url).send() {
-            Ok(response) => {
-                let html = response.text()?;
-                
-                // 尝试解析 HTML
-                match Self::parse_html(&html, max_count) {
-                    Ok(records) if !records.is_empty() => {
-                        println!("成功从网络获取 {} 条记录", records.len());
-                        return Ok(records);
-                    }
-                    Err(e) => {
-                        println!("解析网页失败: {}, 使用示例数据", e);
-                    }
-                    _ => {
-                        println!("未解析到数据，使用示例数据");
-                    }
+        let mut attempt = 0;
+        loop {
+            match client.get(url).send().and_then(|response| response.text()) {
+                Ok(body) => return Ok(body),
+                Err(e) if attempt < config.max_retries => {
+                    attempt += 1;
+                    println!("请求失败: {}，第 {} 次重试...", e, attempt);
                 }
-            }
-            Err(e) => {
-                println!("网络请求失败: {}, 使用示例数据", e);
+                Err(e) => return Err(e.into()),
             }
         }
-        
-        // 如果网络获取失败，返回示例数据
-        println!("提示：使用示例数据进行演示");
-        Self::generate_sample_data(max_count)
     }
 
-    fn parse_html(html: &str, max_count: usize) -> Result<Vec<SsqRecord>> {
+    /// 从福彩网 JSON 接口获取最近 `max_count` 期数据（按期号升序）
+    pub fn fetch_history_json(max_count: usize, config: &FetchConfig) -> Result<Vec<SsqRecord>> {
+        let url = format!(
+            "https://www.cwl.gov.cn/cwl_admin/front/cwlkj/search/kjxx/findDrawNotice?name=ssq&issueCount={}",
+            max_count.min(500)
+        );
+        let body = Self::fetch_url(&url, config)?;
+        Self::cache_raw(&body, "json", config);
+        let records = Self::parse_json(&body)?;
+        if records.is_empty() {
+            bail!("JSON 接口没有返回有效的开奖数据");
+        }
+        Ok(records)
+    }
+
+    /// 解析 JSON 接口的响应，跳过字段无效的记录
+    ///
+    /// 接口的期号是四位年份（如 `2024001`），转换为与网页数据一致的两位年份（`24001`），
+    /// 避免与本地数据合并时同一期出现两种期号。
+    pub(crate) fn parse_json(body: &str) -> Result<Vec<SsqRecord>> {
+        let response: CwlResponse = serde_json::from_str(body)?;
+        if response.state != 0 {
+            bail!("JSON 接口返回错误: {}", response.message);
+        }
+
+        let mut records: Vec<SsqRecord> = response
+            .result
+            .into_iter()
+            .filter_map(|draw| {
+                let issue = match draw.code.len() {
+                    7 => draw.code[2..].to_string(),
+                    _ => draw.code,
+                };
+                if issue.is_empty() || !issue.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+
+                let date = Self::normalize_date(&draw.date)?;
+                let mut red_balls: Vec<u8> = draw
+                    .red
+                    .split(',')
+                    .map(|ball| ball.trim().parse().ok())
+                    .collect::<Option<_>>()?;
+                let blue_ball: u8 = draw.blue.trim().parse().ok()?;
+                if red_balls.len() != 6
+                    || red_balls.iter().any(|&ball| !(1..=33).contains(&ball))
+                    || !(1..=16).contains(&blue_ball)
+                {
+                    return None;
+                }
+                red_balls.sort();
+
+                Some(SsqRecord::new(issue, date, red_balls, blue_ball))
+            })
+            .collect();
+
+        DataManager::sort_chronologically(&mut records);
+        Ok(records)
+    }
+
+    /// 解析 500.com 的历史数据表，没有日期列的行以 `today` 作为开奖日期
+    pub(crate) fn parse_html(
+        html: &str,
+        max_count: usize,
+        today: chrono::NaiveDate,
+    ) -> (Vec<SsqRecord>, ParseDiagnostics) {
         let document = Html::parse_document(html);
         
         // 参考实际 HTML 结构：
@@ -70,9 +358,15 @@ impl DataFetcher {
         ];
         
         let mut records = Vec::new();
+        let mut diagnostics = ParseDiagnostics::default();
         
         for selector_str in selectors {
             println!("尝试选择器: {}", selector_str);
+            let mut attempt = SelectorAttempt {
+                selector: selector_str.to_string(),
+                ..Default::default()
+            };
+
             if let Ok(row_selector) = Selector::parse(selector_str) {
                 let td_selector = Selector::parse("td").unwrap();
                 
@@ -80,6 +374,7 @@ impl DataFetcher {
                 println!("找到 {} 行数据", rows.len());
                 
                 for (row_idx, row) in rows.iter().enumerate() {
+                    attempt.rows_scanned += 1;
                     let cells: Vec<String> = row
                         .select(&td_selector)
                         .map(|cell| cell.text().collect::<String>().trim().to_string())
@@ -91,12 +386,18 @@ impl DataFetcher {
                     
                     // 至少需要 8 列：期号(1) + 红球(6) + 蓝球(1)
                     if cells.len() < 8 {
+                        attempt.too_few_columns += 1;
                         continue;
                     }
 
                     // 第1列：期号（索引0）
                     let issue = cells[0].trim().to_string();
                     if issue.is_empty() || !issue.chars().all(|c| c.is_numeric()) {
+                        attempt.invalid_issue += 1;
+                        attempt.failures.push(RowFailure {
+                            row: row_idx,
+                            reason: format!("期号无效: {:?}", issue),
+                        });
                         continue;
                     }
 
@@ -122,6 +423,26 @@ impl DataFetcher {
                         if row_idx < 3 {
                             println!("行 {} 红球解析失败: {:?}", row_idx, &cells[1..7]);
                         }
+                        attempt.red_parse_failed += 1;
+                        attempt.failures.push(RowFailure {
+                            row: row_idx,
+                            reason: format!("期号 {} 红球无效: {:?}", issue, &cells[1..7]),
+                        });
+                        continue;
+                    }
+
+                    // 源数据出错时可能出现重复的红球，这样的记录不能入库
+                    if let Some((idx, &ball)) = red_balls
+                        .iter()
+                        .enumerate()
+                        .find(|(idx, ball)| red_balls[..*idx].contains(ball))
+                    {
+                        println!("行 {} 红球重复，已跳过: {} (第 {} 个)", row_idx, ball, idx + 1);
+                        attempt.duplicate_reds += 1;
+                        attempt.failures.push(RowFailure {
+                            row: row_idx,
+                            reason: format!("期号 {} 红球重复: {:?}", issue, &cells[1..7]),
+                        });
                         continue;
                     }
 
@@ -132,42 +453,90 @@ impl DataFetcher {
                             if row_idx < 3 {
                                 println!("行 {} 蓝球解析失败: {}", row_idx, cells[7]);
                             }
+                            attempt.blue_parse_failed += 1;
+                            attempt.failures.push(RowFailure {
+                                row: row_idx,
+                                reason: format!("期号 {} 蓝球无效: {:?}", issue, cells[7]),
+                            });
                             continue;
                         }
                     };
 
-                    // 日期在最后一列
+                    // 日期在最后一列，统一转换为 YYYY-MM-DD
                     let date = if cells.len() > 10 {
-                        cells[cells.len() - 1].trim().to_string()
+                        let raw_date = cells[cells.len() - 1].trim();
+                        match Self::normalize_date(raw_date) {
+                            Some(date) => date,
+                            None => {
+                                println!("行 {} 日期无法解析，已跳过: {}", row_idx, raw_date);
+                                attempt.date_parse_failed += 1;
+                                attempt.failures.push(RowFailure {
+                                    row: row_idx,
+                                    reason: format!("期号 {} 日期无法解析: {:?}", issue, raw_date),
+                                });
+                                continue;
+                            }
+                        }
                     } else {
-                        chrono::Local::now().format("%Y-%m-%d").to_string()
+                        today.format("%Y-%m-%d").to_string()
                     };
 
-                    records.push(SsqRecord::new(issue, date, red_balls, blue_ball));
+                    let mut record = SsqRecord::new(issue, date, red_balls, blue_ball);
+                    record.sort_red_balls();
+                    records.push(record);
+                    attempt.parsed += 1;
 
                     if records.len() >= max_count {
                         break;
                     }
                 }
+            }
+
+            diagnostics.attempts.push(attempt);
                 
-                // 如果找到了记录，就不再尝试其他选择器
-                if !records.is_empty() {
-                    println!("✅ 使用选择器 '{}' 成功解析 {} 条记录", selector_str, records.len());
-                    break;
-                }
+            // 如果找到了记录，就不再尝试其他选择器
+            if !records.is_empty() {
+                println!("✅ 使用选择器 '{}' 成功解析 {} 条记录", selector_str, records.len());
+                diagnostics.selector = Some(selector_str.to_string());
+                break;
             }
         }
 
         if records.is_empty() {
-            return Err(anyhow!("未解析到任何有效数据"));
+            println!("未解析到任何有效数据: {}", diagnostics.summary());
+        } else {
+            let failures = diagnostics.failures();
+            println!("成功解析 {} 条记录，{} 行解析失败", records.len(), failures.len());
+            for failure in failures {
+                println!("  第 {} 行: {}", failure.row, failure.reason);
+            }
         }
 
-        println!("成功解析 {} 条记录", records.len());
-        Ok(records)
+        (records, diagnostics)
     }
 
-    /// 生成示例数据用于测试
-    fn generate_sample_data(count: usize) -> Result<Vec<SsqRecord>> {
+    /// 将网页中的日期统一为 `%Y-%m-%d` 格式
+    ///
+    /// 支持 `2024-01-01`、`2024/01/01`、`2024.01.01`、`2024年01月01日`、`20240101`，
+    /// 以及带星期后缀的 `2024-01-01(一)`。无法识别时返回 `None`。
+    fn normalize_date(raw: &str) -> Option<String> {
+        const DATE_FORMATS: [&str; 5] = ["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d", "%Y年%m月%d日", "%Y%m%d"];
+
+        // 去掉 "(一)"、"（日）" 之类的星期后缀
+        let trimmed = raw
+            .split(['(', '（'])
+            .next()
+            .unwrap_or(raw)
+            .trim();
+
+        DATE_FORMATS
+            .iter()
+            .find_map(|format| chrono::NaiveDate::parse_from_str(trimmed, format).ok())
+            .map(|date| date.format("%Y-%m-%d").to_string())
+    }
+
+    /// 生成示例数据用于测试，日期从 `today` 起每期往前推三天
+    fn generate_sample_data(count: usize, today: chrono::NaiveDate) -> Result<Vec<SsqRecord>> {
         use rand::Rng;
         let mut rng = rand::thread_rng();
         let mut records = Vec::new();
@@ -176,7 +545,7 @@ impl DataFetcher {
         
         for i in 0..count.min(500) {
             let issue = format!("{}", base_issue + i);
-            let date = chrono::Local::now()
+            let date = today
                 .checked_sub_signed(chrono::Duration::days(i as i64 * 3))
                 .unwrap()
                 .format("%Y-%m-%d")
@@ -195,13 +564,224 @@ impl DataFetcher {
             // 生成1个蓝球（1-16）
             let blue_ball = rng.gen_range(1..=16);
             
-            records.push(SsqRecord::new(issue, date, red_balls, blue_ball));
+            let mut record = SsqRecord::new(issue, date, red_balls, blue_ball);
+            record.synthetic = true;
+            records.push(record);
         }
         
-        // 按期号排序
-        records.sort_by(|a, b| a.issue.cmp(&b.issue));
+        DataManager::sort_chronologically(&mut records);
         
         Ok(records)
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// 按调用顺序记录被请求的数据源名称
+    type CallLog = Rc<RefCell<Vec<&'static str>>>;
+
+    /// 测试用数据源：返回固定结果并记录调用，`records` 为 `None` 时返回错误
+    struct MockSource {
+        name: &'static str,
+        records: Option<Vec<SsqRecord>>,
+        log: CallLog,
+    }
+
+    impl MockSource {
+        fn boxed(
+            name: &'static str,
+            records: Option<Vec<SsqRecord>>,
+            log: &CallLog,
+        ) -> Box<dyn RecordSource> {
+            Box::new(Self {
+                name,
+                records,
+                log: Rc::clone(log),
+            })
+        }
+    }
+
+    impl RecordSource for MockSource {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn fetch(&self, max_count: usize) -> Result<Vec<SsqRecord>> {
+            self.log.borrow_mut().push(self.name);
+            match &self.records {
+                Some(records) => Ok(records.iter().take(max_count).cloned().collect()),
+                None => bail!("{} 不可用", self.name),
+            }
+        }
+    }
+
+    /// 测试中固定的"今天"
+    fn today() -> chrono::NaiveDate {
+        chrono::NaiveDate::from_ymd_opt(2024, 6, 2).unwrap()
+    }
+
+    fn mock_record(issue: &str) -> SsqRecord {
+        SsqRecord::new(issue.to_string(), "2024-01-02".to_string(), vec![1, 2, 3, 4, 5, 6], 1)
+    }
+
+    /// 仿照 500.com 历史数据表的一行：期号、6 个红球、蓝球、若干奖池/奖金列，日期在最后一列
+    fn html_row(issue: &str, reds: [&str; 6], blue: &str, date: &str) -> String {
+        let mut cells = vec![issue.to_string()];
+        cells.extend(reds.iter().map(|ball| ball.to_string()));
+        cells.push(blue.to_string());
+        let prizes = ["", "1,234,567", "5", "5,000,000", "100", "200,000", "300,000,000"];
+        cells.extend(prizes.map(String::from));
+        cells.push(date.to_string());
+        let tds: String = cells.iter().map(|cell| format!("<td>{}</td>", cell)).collect();
+        format!("<tr class=\"t_tr1\">{}</tr>", tds)
+    }
+
+    fn html_page(rows: &[String]) -> String {
+        format!(
+            "<html><body><table><tbody id=\"tdata\">{}</tbody></table></body></html>",
+            rows.concat()
+        )
+    }
+
+    #[test]
+    fn normalize_date_accepts_source_formats() {
+        let formats = ["2024-01-02", "2024/01/02", "2024.01.02", "2024年01月02日", "20240102", "2024-01-02(二)"];
+        for raw in formats {
+            assert_eq!(DataFetcher::normalize_date(raw).as_deref(), Some("2024-01-02"), "{}", raw);
+        }
+        assert_eq!(DataFetcher::normalize_date("02/01/2024"), None);
+        assert_eq!(DataFetcher::normalize_date(""), None);
+    }
+
+    #[test]
+    fn parse_html_stores_iso_dates_and_skips_unparsable_ones() {
+        let html = html_page(&[
+            html_row("24001", ["01", "05", "12", "18", "25", "33"], "07", "2024-01-02"),
+            html_row("24002", ["03", "08", "14", "20", "27", "31"], "11", "2024/01/04"),
+            html_row("24003", ["02", "06", "13", "19", "26", "32"], "09", "昨天"),
+        ]);
+
+        let (records, diagnostics) = DataFetcher::parse_html(&html, 100, today());
+        let dates: Vec<&str> = records.iter().map(|r| r.date.as_str()).collect();
+        assert_eq!(dates, ["2024-01-02", "2024-01-04"]);
+        assert_eq!(diagnostics.attempts[0].date_parse_failed, 1);
+    }
+
+    /// 福彩网接口的响应（节选），额外字段应被忽略
+    const CWL_SAMPLE: &str = r#"{
+        "state": 0, "message": "查询成功", "pageCount": 1, "countNum": 0, "Tflag": 0,
+        "result": [
+            {"name": "双色球", "code": "2024150", "detailsLink": "/c/2024/12/29/1.shtml",
+             "videoLink": "", "date": "2024-12-29(日)", "week": "日",
+             "red": "03,08,13,22,27,33", "blue": "05", "blue2": "", "sales": "382736890",
+             "poolmoney": "2193451215", "content": "北京1注", "addmoney": "",
+             "prizegrades": [{"type": 1, "typenum": "7", "typemoney": "7211298"}]},
+            {"name": "双色球", "code": "2024149", "date": "2024-12-26(四)",
+             "red": "01,09,12,18,25,32", "blue": "14"},
+            {"name": "双色球", "code": "2024148", "date": "2024-12-24(二)",
+             "red": "01,09,12,18,25", "blue": "14"}
+        ]
+    }"#;
+
+    #[test]
+    fn parse_json_reads_a_captured_response() {
+        let records = DataFetcher::parse_json(CWL_SAMPLE).unwrap();
+        // 红球不足 6 个的记录被跳过，其余按期号升序，期号转为两位年份
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].issue, "24149");
+        assert_eq!(records[0].date, "2024-12-26");
+        assert_eq!(records[1].issue, "24150");
+        assert_eq!(records[1].red_balls(), [3, 8, 13, 22, 27, 33]);
+        assert_eq!(records[1].blue_ball, 5);
+    }
+
+    #[test]
+    fn parse_json_rejects_error_responses() {
+        let body = r#"{"state": 1, "message": "访问过于频繁", "result": []}"#;
+        let error = DataFetcher::parse_json(body).unwrap_err();
+        assert!(error.to_string().contains("访问过于频繁"));
+    }
+
+    #[test]
+    fn parse_html_stores_reds_sorted() {
+        let row = html_row("24001", ["18", "05", "33", "01", "25", "12"], "07", "2024-01-02");
+        let (records, _) = DataFetcher::parse_html(&html_page(&[row]), 100, today());
+        assert_eq!(records[0].red_balls(), [1, 5, 12, 18, 25, 33]);
+    }
+
+    #[test]
+    fn parse_html_rejects_rows_with_duplicate_reds() {
+        let html = html_page(&[
+            html_row("24001", ["01", "05", "12", "18", "25", "33"], "07", "2024-01-02"),
+            html_row("24002", ["03", "08", "08", "20", "27", "31"], "11", "2024-01-04"),
+            html_row("24003", ["02", "06", "13", "19", "26", "32"], "09", "2024-01-07"),
+        ]);
+
+        let (records, diagnostics) = DataFetcher::parse_html(&html, 100, today());
+        let issues: Vec<&str> = records.iter().map(|r| r.issue.as_str()).collect();
+        assert_eq!(issues, ["24001", "24003"]);
+        let attempt = &diagnostics.attempts[0];
+        assert_eq!(attempt.duplicate_reds, 1);
+        assert_eq!(attempt.red_parse_failed, 0);
+        assert!(attempt.failures[0].reason.contains("24002"));
+    }
+
+    #[test]
+    fn fetch_from_uses_the_first_source_that_succeeds() {
+        let log = CallLog::default();
+        let sources = vec![
+            MockSource::boxed("first", Some(vec![mock_record("24001")]), &log),
+            MockSource::boxed("second", Some(vec![mock_record("24002")]), &log),
+        ];
+        let (records, source) = DataFetcher::fetch_from(&sources, 10, today()).unwrap();
+        assert!(matches!(source, DataSource::Network));
+        assert_eq!(records[0].issue, "24001");
+        assert_eq!(*log.borrow(), ["first"]);
+    }
+
+    #[test]
+    fn fetch_from_falls_through_failing_and_empty_sources() {
+        let log = CallLog::default();
+        let sources = vec![
+            MockSource::boxed("broken", None, &log),
+            MockSource::boxed("empty", Some(Vec::new()), &log),
+            MockSource::boxed("working", Some(vec![mock_record("24003")]), &log),
+            MockSource::boxed("unused", Some(vec![mock_record("24004")]), &log),
+        ];
+        let (records, source) = DataFetcher::fetch_from(&sources, 10, today()).unwrap();
+        assert!(matches!(source, DataSource::Network));
+        assert_eq!(records[0].issue, "24003");
+        assert_eq!(*log.borrow(), ["broken", "empty", "working"]);
+    }
+
+    #[test]
+    fn fetch_from_falls_back_to_sample_data_when_all_sources_fail() {
+        let log = CallLog::default();
+        let sources = vec![
+            MockSource::boxed("broken", None, &log),
+            MockSource::boxed("empty", Some(Vec::new()), &log),
+        ];
+        let (records, source) = DataFetcher::fetch_from(&sources, 20, today()).unwrap();
+        assert_eq!(*log.borrow(), ["broken", "empty"]);
+        assert!(matches!(source, DataSource::Sample));
+        assert_eq!(records.len(), 20);
+        assert!(records.iter().all(|record| record.synthetic));
+        // 示例数据的日期按传入的今天生成，不取本机时钟
+        assert_eq!(
+            records.iter().map(|r| r.date.as_str()).max(),
+            Some("2024-06-02")
+        );
+    }
+
+    #[test]
+    fn parse_html_dates_rows_without_a_date_column_as_today() {
+        let row = "<tr class=\"t_tr1\"><td>24061</td><td>01</td><td>05</td><td>12</td>\
+                   <td>18</td><td>25</td><td>33</td><td>07</td></tr>";
+        let (records, _) = DataFetcher::parse_html(&html_page(&[row.to_string()]), 100, today());
+        assert_eq!(records[0].date, "2024-06-02");
+    }
+}
@@ -1,20 +1,360 @@
-mod models;
-mod data_manager;
+pub mod models;
+pub mod data_manager;
 mod fetcher;
-mod analyzer;
+pub mod analyzer;
 
-use analyzer::Analyzer;
-use data_manager::DataManager;
-use fetcher::DataFetcher;
-use models::{AlgorithmType, BallFrequency, PredictionResult, SsqRecord};
+use std::sync::{Mutex, MutexGuard};
+
+use analyzer::{
+    Analyzer, FrequencyTracker, CO_OVERDUE_MIN_OMISSION, DRAW_UTC_OFFSET_HOURS, PREDICTION_COUNT, RED_ZONES,
+    WHEEL_POOL_SIZES,
+};
+use data_manager::{DataManager, MAX_RECORDS};
+use fetcher::{CsvFileSource, DataFetcher, FetchConfig, RecordSource, DEFAULT_TIMEOUT_SECS};
+use models::{
+    AbsenceRun, AlgorithmProfile, AlgorithmType, AnalysisRequest, AnalysisResponse,
+    AnalysisSnapshot, AnomalyReport, BacktestReport, BallFrequency, BankrollReport, BlueStrategy,
+    CombinationRank, CoverageReport, DataSource, DatasetDiff, DatasetFingerprint, DrawFilter,
+    DueForecast, ExpectedMatches, FairPredictions, FairnessReport, GradeReport, HealthReport,
+    HotSixReport, IssueNumber, IssueSequenceReport, LoadResult, MergePolicy, MergeReport,
+    NumberStatCard, ParseDiagnostics, PlaySimulation, PredictionOptions, PredictionResult,
+    RecencyComparison, RedPair, RelationshipModel, ReparseReport, SelectionReport, SnapshotSections,
+    SsqRecord, StreakReport, TemperatureWeights, TicketCheck, WeekdayFrequency, WeightInterval,
+};
+use tauri::State;
+
+/// 已加载的历史数据，由 `load_and_update_data` / `refresh_records` 写入
+type RecordState = Mutex<Vec<SsqRecord>>;
+
+fn lock_records<'a>(state: &'a State<'_, RecordState>) -> Result<MutexGuard<'a, Vec<SsqRecord>>, String> {
+    state.lock().map_err(|_| "数据状态已损坏".to_string())
+}
+
+/// 数据加载设置，由 `load_and_update_data` 写入，重新加载、导入等操作沿用同样的设置
+#[derive(Debug, Clone, Copy)]
+struct DataSettings {
+    /// 分析时保留的最大期数
+    max_records: usize,
+    /// 判断"今天"所用时区的 UTC 偏移（小时）
+    utc_offset_hours: i32,
+}
+
+impl Default for DataSettings {
+    fn default() -> Self {
+        Self {
+            max_records: MAX_RECORDS,
+            utc_offset_hours: DRAW_UTC_OFFSET_HOURS,
+        }
+    }
+}
+
+impl DataSettings {
+    /// 所设时区的今天
+    fn today(&self) -> chrono::NaiveDate {
+        Analyzer::date_in_timezone(chrono::Utc::now(), self.utc_offset_hours)
+    }
+
+    /// 沿用当前时区、其余参数取默认值的网络请求设置
+    fn fetch_config(&self) -> FetchConfig {
+        FetchConfig {
+            utc_offset_hours: self.utc_offset_hours,
+            ..FetchConfig::default()
+        }
+    }
+}
+
+type SettingsState = Mutex<DataSettings>;
+
+fn lock_settings<'a>(settings: &'a State<'_, SettingsState>) -> Result<MutexGuard<'a, DataSettings>, String> {
+    settings.lock().map_err(|_| "设置状态已损坏".to_string())
+}
+
+/// 按当前设置创建 `DataManager`
+fn data_manager(settings: &State<'_, SettingsState>) -> Result<DataManager, String> {
+    let settings = *lock_settings(settings)?;
+    Ok(DataManager::new()
+        .map_err(|e| e.to_string())?
+        .with_max_records(settings.max_records)
+        .with_utc_offset_hours(settings.utc_offset_hours))
+}
+
+fn parse_algorithm(algorithm: &str) -> Result<AlgorithmType, String> {
+    match algorithm {
+        "hot" => Ok(AlgorithmType::HotStaysHot),
+        "cold" => Ok(AlgorithmType::ColdBounceBack),
+        "realistic" => Ok(AlgorithmType::Realistic),
+        _ => Err("无效的算法类型".to_string()),
+    }
+}
+
+/// 根据开奖日程判断是否需要从网络获取，并给出原因
+///
+/// 只有在最后更新之后又有开奖日已经过去时才认为数据过期，
+/// 这样周一、三、五、六这些非开奖日不会重复请求网络。
+fn decide_fetch(
+    local_empty: bool,
+    last_update: Option<chrono::NaiveDate>,
+    today: chrono::NaiveDate,
+) -> (bool, String) {
+    if local_empty {
+        return (true, "本地无数据，需要从网络获取".to_string());
+    }
+
+    let Some(last_update_date) = last_update else {
+        return (true, "无法获取最后更新时间，需要从网络获取".to_string());
+    };
+
+    // 最后更新当天若是开奖日，更新时可能尚未开奖，因此从最后更新当天开始找
+    let next_draw = Analyzer::next_draw_date(last_update_date);
+    if next_draw < today {
+        (
+            true,
+            format!(
+                "数据不是最新（最后更新: {}，其后 {} 已开奖，今天: {}），需要从网络获取",
+                last_update_date, next_draw, today
+            ),
+        )
+    } else {
+        (
+            false,
+            format!(
+                "数据已是最新（最后更新: {}，下次开奖: {}，今天: {}），无需重新获取",
+                last_update_date, next_draw, today
+            ),
+        )
+    }
+}
+
+/// 按保留策略裁剪到最近 `max_records` 期用于分析，并记录被忽略的期号
+fn retain_with_log(records: Vec<SsqRecord>, max_records: usize) -> Vec<SsqRecord> {
+    let (kept, dropped) = DataManager::retain_recent(records, max_records);
+    if !dropped.is_empty() {
+        println!(
+            "超出保留上限 {} 期，分析时忽略 {} 条最早的记录: {}",
+            max_records,
+            dropped.len(),
+            dropped.join(", ")
+        );
+    }
+    kept
+}
+
+/// 校验一注号码：6 个互不重复的红球（1-33）和 1 个蓝球（1-16）
+fn validate_ticket(red_balls: &[u8], blue_ball: u8) -> Result<(), String> {
+    validate_reds(red_balls)?;
+    validate_number(blue_ball, true)
+}
+
+/// 校验 6 个互不重复的红球（1-33）
+fn validate_reds(red_balls: &[u8]) -> Result<(), String> {
+    if red_balls.len() != 6 {
+        return Err(format!("红球必须是 6 个，实际 {} 个", red_balls.len()));
+    }
+    for (idx, &ball) in red_balls.iter().enumerate() {
+        validate_number(ball, false)?;
+        if red_balls[..idx].contains(&ball) {
+            return Err(format!("红球不能重复: {}", ball));
+        }
+    }
+    Ok(())
+}
+
+/// 环境检查时连通性探测的超时时间（秒）
+const HEALTHCHECK_TIMEOUT_SECS: u64 = 5;
+
+/// 网络请求超时的上限（秒）
+const MAX_TIMEOUT_SECS: u64 = 300;
+
+/// 网络请求重试次数的上限
+const MAX_FETCH_RETRIES: u32 = 5;
+
+/// 一次机选的最大注数
+const MAX_LUCKY_DIP_TICKETS: usize = 100;
+
+/// 红球三元组最多返回的个数
+const MAX_TOP_TRIPLETS: usize = 200;
+
+/// 自助法默认的重采样次数
+const DEFAULT_BOOTSTRAP_ITERATIONS: usize = 200;
+
+/// 自助法重采样次数的上限
+const MAX_BOOTSTRAP_ITERATIONS: usize = 2000;
+
+/// 回测最多覆盖的期数，每期都要重新生成一次预测
+const MAX_BACKTEST_DRAWS: usize = 100;
+
+/// 模拟投注最多覆盖的期数
+const MAX_SIMULATED_DRAWS: usize = 1000;
+
+/// 校验用作随机种子的熵字符串及与之冲突的选项
+fn validate_entropy(entropy: &str, options: &PredictionOptions) -> Result<(), String> {
+    if entropy.is_empty() {
+        return Err("熵字符串不能为空".to_string());
+    }
+    // 时间预算会让迭代次数随机器快慢变化，结果就无法复现
+    if options.max_duration_ms.is_some() {
+        return Err("指定熵字符串时不能同时设置时间预算".to_string());
+    }
+    Ok(())
+}
+
+/// 校验预测选项
+fn validate_options(options: &PredictionOptions) -> Result<(), String> {
+    // 6 个红球在 1-33 内，相邻间距最大只能是 6
+    if options.min_gap > 6 {
+        return Err(format!("红球最小间距 {} 过大，最大为 6", options.min_gap));
+    }
+
+    if let Some(t) = options.temperature {
+        if !t.is_finite() || t <= 0.0 {
+            return Err("温度必须是大于 0 的数".to_string());
+        }
+    }
+
+    if let Some(decay) = options.recency_decay {
+        validate_decay(decay)?;
+    }
+
+    if let Some(ratio) = options.zone_ratio {
+        if ratio.iter().map(|&count| count as u32).sum::<u32>() != 6 {
+            return Err(format!("三区比 {:?} 的红球总数必须为 6", ratio));
+        }
+        for (&count, &(lo, hi)) in ratio.iter().zip(RED_ZONES.iter()) {
+            if count > hi - lo + 1 {
+                return Err(format!("{}-{} 区只有 {} 个号码，不能选 {} 个", lo, hi, hi - lo + 1, count));
+            }
+        }
+    }
+
+    if options.max_duration_ms == Some(0) {
+        return Err("时间预算必须大于 0 毫秒".to_string());
+    }
+
+    if let Some(distinctness) = &options.distinctness {
+        if distinctness.recent_draws == 0 {
+            return Err("比较的近期期数必须大于 0".to_string());
+        }
+        if !(0.0..=1.0).contains(&distinctness.weight) {
+            return Err("差异度占比必须在 0-1 之间".to_string());
+        }
+    }
+
+    if let Some(belief) = options.belief {
+        if !(0.0..=1.0).contains(&belief) {
+            return Err("信任度必须在 0-1 之间".to_string());
+        }
+    }
+
+    if let Some(entropy) = &options.entropy {
+        validate_entropy(entropy, options)?;
+    }
+
+    if options.blue_from_sum && options.blue_pool.is_some() {
+        return Err("按和值推出蓝球时不能同时限定蓝球范围".to_string());
+    }
+
+    // 沿用的上一期蓝球可能不在限定范围内
+    if options.include_last_blue && options.blue_pool.is_some() {
+        return Err("沿用上一期蓝球时不能同时限定蓝球范围".to_string());
+    }
+
+    if let Some(pool) = &options.blue_pool {
+        if pool.is_empty() {
+            return Err("蓝球范围不能为空".to_string());
+        }
+        for (idx, &ball) in pool.iter().enumerate() {
+            validate_number(ball, true)?;
+            if pool[..idx].contains(&ball) {
+                return Err(format!("蓝球范围中有重复号码: {}", ball));
+            }
+        }
+    }
+
+    if let Some(penalty) = &options.shape_penalty {
+        for width in [penalty.sum_width, penalty.span_width].into_iter().flatten() {
+            if !width.is_finite() || width <= 0.0 {
+                return Err("和值/跨度惩罚宽度必须是大于 0 的数".to_string());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_decay(decay: f64) -> Result<(), String> {
+    if !decay.is_finite() || decay <= 0.0 || decay > 1.0 {
+        return Err("时间衰减系数必须在 (0, 1] 之间".to_string());
+    }
+    Ok(())
+}
+
+/// 校验号码是否在对应颜色的范围内
+fn validate_number(number: u8, is_blue: bool) -> Result<(), String> {
+    let (min, max) = Analyzer::ball_range(is_blue);
+    if number < min || number > max {
+        let color = if is_blue { "蓝球" } else { "红球" };
+        return Err(format!("{}号码必须在 {}-{} 之间: {}", color, min, max, number));
+    }
+    Ok(())
+}
 
 #[tauri::command]
-fn load_and_update_data() -> Result<Vec<SsqRecord>, String> {
-    let manager = DataManager::new().map_err(|e| e.to_string())?;
+#[allow(clippy::too_many_arguments)]
+fn load_and_update_data(
+    state: State<'_, RecordState>,
+    settings: State<'_, SettingsState>,
+    max_records: Option<usize>,
+    migrate_legacy: Option<bool>,
+    timeout_secs: Option<u64>,
+    max_retries: Option<u32>,
+    merge_policy: Option<MergePolicy>,
+    utc_offset_hours: Option<i32>,
+    cache_raw: Option<bool>,
+) -> Result<LoadResult, String> {
+    let max_records = max_records.unwrap_or(MAX_RECORDS);
+    if max_records == 0 {
+        return Err("保留期数必须大于 0".to_string());
+    }
+    let utc_offset_hours = utc_offset_hours.unwrap_or(DRAW_UTC_OFFSET_HOURS);
+    if !(-12..=14).contains(&utc_offset_hours) {
+        return Err("时区偏移必须在 -12 到 14 小时之间".to_string());
+    }
+    let fetch_config = FetchConfig {
+        timeout_secs: timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS),
+        max_retries: max_retries.unwrap_or(0),
+        cache_raw: cache_raw.unwrap_or(false),
+        utc_offset_hours,
+    };
+    if !(1..=MAX_TIMEOUT_SECS).contains(&fetch_config.timeout_secs) {
+        return Err(format!("超时时间必须在 1-{} 秒之间", MAX_TIMEOUT_SECS));
+    }
+    if fetch_config.max_retries > MAX_FETCH_RETRIES {
+        return Err(format!("重试次数不能超过 {} 次", MAX_FETCH_RETRIES));
+    }
+    let manager = DataManager::new()
+        .map_err(|e| e.to_string())?
+        .with_max_records(max_records)
+        .with_utc_offset_hours(utc_offset_hours);
+    // 记住保留期数和时区，之后重新加载、导入和按日期判断时沿用同样的设置
+    *lock_settings(&settings)? = DataSettings {
+        max_records,
+        utc_offset_hours,
+    };
     
+    // 旧版 CSV 的红球顺序不统一，默认在加载前一次性迁移
+    let mut warnings = Vec::new();
+    if migrate_legacy.unwrap_or(true) {
+        if let Some(changed) = manager.migrate_legacy_csv().map_err(|e| e.to_string())? {
+            warnings.push(format!("已将旧版本地 CSV 的红球统一排序（调整 {} 条记录）", changed));
+        }
+    }
+
     // 1. 首先尝试加载本地 CSV 数据
     println!("正在加载本地 CSV 数据...");
-    let local_records = manager.load_local_data().map_err(|e| e.to_string())?;
+    // 完整数据用于合并后写回，裁剪后的数据用于分析
+    let all_local_records = manager.load_all_local_data().map_err(|e| e.to_string())?;
+    let (local_records, load_warning) = manager.retain_with_warning(all_local_records.clone());
+    warnings.extend(load_warning);
     
     if !local_records.is_empty() {
         let latest = local_records.last().unwrap();
@@ -26,60 +366,75 @@ fn load_and_update_data() -> Result<Vec<SsqRecord>, String> {
     
     // 2. 检查最后更新时间
     let last_update = manager.get_last_update_time().map_err(|e| e.to_string())?;
-    let today = chrono::Local::now().date_naive();
+    let today = manager.today();
     
-    let should_fetch = if local_records.is_empty() {
-        println!("本地无数据，需要从网络获取");
-        true
-    } else if let Some(last_update_date) = last_update {
-        if last_update_date >= today {
-            println!("数据已是最新（最后更新: {}，今天: {}），无需重新获取", last_update_date, today);
-            false
-        } else {
-            println!("数据不是最新（最后更新: {}，今天: {}），需要从网络获取", last_update_date, today);
-            true
-        }
-    } else {
-        println!("无法获取最后更新时间，需要从网络获取");
-        true
-    };
+    let (should_fetch, fetch_reason) = decide_fetch(local_records.is_empty(), last_update, today);
+    println!("{}", fetch_reason);
     
     // 3. 根据判断结果，决定最终返回的数据
-    let final_records = if should_fetch {
+    let result = if should_fetch {
         println!("正在从网络获取最新数据...");
         
-        match DataFetcher::fetch_history(500) {
-            Ok(new_records) => {
+        match DataFetcher::fetch_history(500, &fetch_config) {
+            Ok((new_records, DataSource::Network)) => {
                 println!("网络获取成功，获取到 {} 条记录", new_records.len());
-                println!("当前本地记录数: {}", local_records.len());
+                println!("当前本地记录数: {}", all_local_records.len());
                 println!("开始合并数据...");
                 
-                // 合并并去重
-                let mut merged_records = local_records;
-                let mut added_count = 0;
-                for new_record in new_records {
-                    if !merged_records.iter().any(|r| r.issue == new_record.issue) {
-                        merged_records.push(new_record);
-                        added_count += 1;
-                    }
+                // 基于完整的本地数据合并，抓取窗口之外的旧记录原样保留
+                let mut added_records: Vec<SsqRecord> = new_records
+                    .iter()
+                    .filter(|r| !all_local_records.iter().any(|local| local.issue == r.issue))
+                    .cloned()
+                    .collect();
+                DataManager::sort_chronologically(&mut added_records);
+                let (merged_records, added_count, corrected) = DataManager::merge_records_with(
+                    all_local_records,
+                    new_records,
+                    merge_policy.unwrap_or_default(),
+                );
+                println!("新增 {} 条记录，修正 {} 条记录", added_count, corrected.len());
+
+                // 只有新开奖时直接追加到 CSV；修正了已有记录时需要完整重写
+                if corrected.is_empty() {
+                    manager.append_records(&added_records).map_err(|e| e.to_string())?;
+                } else {
+                    manager.save_local_data(&merged_records).map_err(|e| e.to_string())?;
+                    warnings.push(format!("数据源修正了 {} 期数据: {}", corrected.len(), corrected.join(", ")));
                 }
-                println!("新增 {} 条记录", added_count);
-                
-                // 按期号排序
-                println!("开始排序...");
-                merged_records.sort_by(|a, b| a.issue.cmp(&b.issue));
-                println!("排序完成");
-                
-                // 保存到 CSV
-                println!("正在保存 {} 条记录到 CSV...", merged_records.len());
-                manager.save_local_data(&merged_records).map_err(|e| e.to_string())?;
                 println!("✅ 数据已更新并保存到 CSV");
+
+                // 只在分析时保留最近 max_records 期
+                let merged_records = retain_with_log(merged_records, manager.max_records());
                 
                 if let Some(latest) = merged_records.last() {
                     println!("最新数据: 期号 {}, 日期 {}", latest.issue, latest.date);
                 }
                 
-                merged_records
+                LoadResult {
+                    records: merged_records,
+                    source: DataSource::Network,
+                    is_sample: false,
+                    last_update: Some(today),
+                    fetch_reason,
+                    warnings,
+                }
+            }
+            Ok((sample_records, _)) if local_records.is_empty() => {
+                // 示例数据只用于演示，不写入 CSV，避免被当作真实历史
+                println!("⚠️ 无本地数据且网络不可用，使用示例数据（不保存）");
+                LoadResult {
+                    records: sample_records,
+                    source: DataSource::Sample,
+                    is_sample: true,
+                    last_update,
+                    fetch_reason,
+                    warnings,
+                }
+            }
+            Ok(_) => {
+                println!("网络不可用，忽略示例数据，将使用现有本地数据");
+                cached_result(local_records, last_update, fetch_reason, warnings)
             }
             Err(e) => {
                 println!("网络获取失败: {}", e);
@@ -87,59 +442,1191 @@ fn load_and_update_data() -> Result<Vec<SsqRecord>, String> {
                     return Err(format!("无本地数据且网络获取失败: {}", e));
                 }
                 println!("将使用现有本地数据");
-                local_records
+                cached_result(local_records, last_update, fetch_reason, warnings)
             }
         }
     } else {
         println!("使用现有本地数据");
-        local_records
+        cached_result(local_records, last_update, fetch_reason, warnings)
     };
     
-    Ok(final_records)
+    *lock_records(&state)? = result.records.clone();
+    Ok(result)
+}
+
+fn cached_result(
+    records: Vec<SsqRecord>,
+    last_update: Option<chrono::NaiveDate>,
+    fetch_reason: String,
+    warnings: Vec<String>,
+) -> LoadResult {
+    LoadResult {
+        records,
+        source: DataSource::Cache,
+        is_sample: false,
+        last_update,
+        fetch_reason,
+        warnings,
+    }
+}
+
+#[tauri::command]
+fn refresh_records(
+    state: State<'_, RecordState>,
+    settings: State<'_, SettingsState>,
+) -> Result<Vec<SsqRecord>, String> {
+    let manager = data_manager(&settings)?;
+    let records = manager.load_local_data().map_err(|e| e.to_string())?;
+    println!("已从本地 CSV 重新加载 {} 条记录", records.len());
+
+    *lock_records(&state)? = records.clone();
+    Ok(records)
+}
+
+/// 取最近 `window` 期记录，`None` 表示全部历史
+fn recent_window(records: &[SsqRecord], window: Option<usize>) -> Result<&[SsqRecord], String> {
+    match window {
+        Some(0) => Err("统计期数必须大于 0".to_string()),
+        Some(window) => Ok(&records[records.len().saturating_sub(window)..]),
+        None => Ok(records),
+    }
 }
 
 #[tauri::command]
 fn analyze_frequency(
-    records: Vec<SsqRecord>,
+    state: State<'_, RecordState>,
     algorithm: String,
+    red_window: Option<usize>,
+    blue_window: Option<usize>,
 ) -> Result<(Vec<BallFrequency>, Vec<BallFrequency>), String> {
-    let algo_type = match algorithm.as_str() {
-        "hot" => AlgorithmType::HotStaysHot,
-        "cold" => AlgorithmType::ColdBounceBack,
-        _ => return Err("无效的算法类型".to_string()),
-    };
-    
-    let red_freq = Analyzer::analyze_red_frequency(&records, algo_type);
-    let blue_freq = Analyzer::analyze_blue_frequency(&records, algo_type);
-    
+    let algo_type = parse_algorithm(&algorithm)?;
+    let records = lock_records(&state)?;
+    windowed_frequencies(&records, algo_type, red_window, blue_window)
+}
+
+/// 红球、蓝球分别按各自的窗口统计频率
+///
+/// 蓝球只有 16 个号码、变化更快，可以单独使用更短的窗口
+fn windowed_frequencies(
+    records: &[SsqRecord],
+    algorithm: AlgorithmType,
+    red_window: Option<usize>,
+    blue_window: Option<usize>,
+) -> Result<(Vec<BallFrequency>, Vec<BallFrequency>), String> {
+    let red_freq = Analyzer::analyze_red_frequency(recent_window(records, red_window)?, algorithm);
+    let blue_freq = Analyzer::analyze_blue_frequency(recent_window(records, blue_window)?, algorithm);
     Ok((red_freq, blue_freq))
 }
 
 #[tauri::command]
 fn generate_predictions(
-    records: Vec<SsqRecord>,
+    state: State<'_, RecordState>,
     algorithm: String,
+    blue_algorithm: Option<String>,
+    options: Option<PredictionOptions>,
 ) -> Result<Vec<PredictionResult>, String> {
-    let algo_type = match algorithm.as_str() {
-        "hot" => AlgorithmType::HotStaysHot,
-        "cold" => AlgorithmType::ColdBounceBack,
-        _ => return Err("无效的算法类型".to_string()),
-    };
+    let algo_type = parse_algorithm(&algorithm)?;
+    // 未指定蓝球算法时与红球一致
+    let blue_algo_type = parse_algorithm(blue_algorithm.as_deref().unwrap_or(&algorithm))?;
+    let options = options.unwrap_or_default();
+    validate_options(&options)?;
+
+    let records = lock_records(&state)?;
     
-    let predictions = Analyzer::generate_predictions(&records, algo_type);
+    let predictions = Analyzer::generate_predictions(&records, algo_type, blue_algo_type, &options);
     Ok(predictions)
 }
 
+#[tauri::command]
+fn next_draw_date(settings: State<'_, SettingsState>, from: Option<String>) -> Result<String, String> {
+    let from_date = match from {
+        Some(date_str) => chrono::NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+            .map_err(|_| format!("无效的日期: {}", date_str))?,
+        None => lock_settings(&settings)?.today(),
+    };
+
+    let next = Analyzer::next_draw_date(from_date);
+    Ok(next.format("%Y-%m-%d").to_string())
+}
+
+#[tauri::command]
+fn absent_numbers(state: State<'_, RecordState>, window: usize, is_blue: bool) -> Result<Vec<u8>, String> {
+    if window == 0 {
+        return Err("统计期数必须大于 0".to_string());
+    }
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::absent_numbers(&records, window, is_blue))
+}
+
+#[tauri::command]
+fn red_correlations(state: State<'_, RecordState>) -> Result<Vec<(RedPair, f64)>, String> {
+    let records = lock_records(&state)?;
+    Ok(Analyzer::red_correlations(&records))
+}
+
+#[tauri::command]
+fn export_ticket_slip(
+    settings: State<'_, SettingsState>,
+    predictions: Vec<PredictionResult>,
+    algorithm: String,
+) -> Result<String, String> {
+    let algo_type = parse_algorithm(&algorithm)?;
+    let today = lock_settings(&settings)?.today();
+    Ok(Analyzer::format_ticket_slip(&predictions, algo_type, today))
+}
+
+#[tauri::command]
+fn debug_fetch(settings: State<'_, SettingsState>) -> Result<ParseDiagnostics, String> {
+    let fetch_config = lock_settings(&settings)?.fetch_config();
+    DataFetcher::fetch_diagnostics(500, &fetch_config).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn rolling_hot_set(
+    state: State<'_, RecordState>,
+    window: usize,
+    algorithm: String,
+) -> Result<(Vec<BallFrequency>, Vec<BallFrequency>), String> {
+    if window == 0 {
+        return Err("统计期数必须大于 0".to_string());
+    }
+
+    let algo_type = parse_algorithm(&algorithm)?;
+    let records = lock_records(&state)?;
+
+    // 逐期推入滑动窗口，超出窗口的最早一期随即移除
+    let mut tracker = FrequencyTracker::new();
+    for record in records.iter() {
+        tracker.add_record(record);
+        if tracker.len() > window {
+            tracker.remove_oldest();
+        }
+    }
+
+    Ok((tracker.red_frequencies(algo_type), tracker.blue_frequencies(algo_type)))
+}
+
+#[tauri::command]
+fn best_case_against_history(
+    state: State<'_, RecordState>,
+    predictions: Vec<PredictionResult>,
+) -> Result<Vec<(PredictionResult, Option<u8>)>, String> {
+    let records = lock_records(&state)?;
+    Ok(Analyzer::best_case_against_history(&predictions, &records))
+}
+
+#[tauri::command]
+fn import_csv(
+    state: State<'_, RecordState>,
+    settings: State<'_, SettingsState>,
+    path: String,
+    delimiter: Option<String>,
+    preserve_order: Option<bool>,
+) -> Result<Vec<SsqRecord>, String> {
+    let delimiter = match delimiter.as_deref() {
+        None | Some(",") => b',',
+        Some(";") => b';',
+        Some("\t") | Some("tab") => b'\t',
+        Some("|") => b'|',
+        Some(other) => return Err(format!("不支持的分隔符: {}", other)),
+    };
+
+    let source = CsvFileSource {
+        path: path.into(),
+        delimiter,
+        preserve_order: preserve_order.unwrap_or(false),
+    };
+    let imported = source.fetch(usize::MAX).map_err(|e| e.to_string())?;
+
+    let manager = data_manager(&settings)?;
+    let local_records = manager.load_all_local_data().map_err(|e| e.to_string())?;
+    let (merged_records, added_count) = DataManager::merge_records(local_records, imported);
+    println!("导入新增 {} 条记录", added_count);
+
+    manager.save_local_data(&merged_records).map_err(|e| e.to_string())?;
+    let merged_records = retain_with_log(merged_records, manager.max_records());
+    *lock_records(&state)? = merged_records.clone();
+    Ok(merged_records)
+}
+
+#[tauri::command]
+fn number_stat_card(
+    state: State<'_, RecordState>,
+    number: u8,
+    is_blue: bool,
+) -> Result<NumberStatCard, String> {
+    validate_number(number, is_blue)?;
+    let records = lock_records(&state)?;
+    Ok(Analyzer::number_stat_card(&records, number, is_blue))
+}
+
+#[tauri::command]
+fn backtest(
+    state: State<'_, RecordState>,
+    algorithm: String,
+    test_draws: usize,
+    options: Option<PredictionOptions>,
+) -> Result<BacktestReport, String> {
+    if test_draws == 0 || test_draws > MAX_BACKTEST_DRAWS {
+        return Err(format!("回测期数必须在 1-{} 之间", MAX_BACKTEST_DRAWS));
+    }
+
+    let algo_type = parse_algorithm(&algorithm)?;
+    let options = options.unwrap_or_default();
+    validate_options(&options)?;
+    let records = lock_records(&state)?;
+    if records.len() < 2 {
+        return Err("数据不足，无法回测".to_string());
+    }
+
+    Ok(Analyzer::backtest(&records, algo_type, &options, test_draws))
+}
+
+#[tauri::command]
+fn sum_tail_distribution(state: State<'_, RecordState>) -> Result<Vec<(u8, usize)>, String> {
+    let records = lock_records(&state)?;
+    Ok(Analyzer::analyze_sum_tail_distribution(&records))
+}
+
+#[tauri::command]
+fn filter_draws(state: State<'_, RecordState>, filter: DrawFilter) -> Result<Vec<SsqRecord>, String> {
+    if let Some(reds) = &filter.required_reds {
+        for &ball in reds {
+            validate_number(ball, false)?;
+        }
+    }
+    if let Some(blue) = filter.blue {
+        validate_number(blue, true)?;
+    }
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::filter_draws(&records, &filter))
+}
+
+#[tauri::command]
+fn rank_combination(
+    state: State<'_, RecordState>,
+    algorithm: String,
+    red_balls: Vec<u8>,
+    blue_ball: u8,
+) -> Result<CombinationRank, String> {
+    validate_ticket(&red_balls, blue_ball)?;
+    let algo_type = parse_algorithm(&algorithm)?;
+    let records = lock_records(&state)?;
+    Ok(Analyzer::rank_combination(&records, algo_type, &red_balls, blue_ball))
+}
+
+#[tauri::command]
+fn recommend_blue(state: State<'_, RecordState>, strategy: String) -> Result<Vec<(u8, f64)>, String> {
+    let strategy = match strategy.as_str() {
+        "frequency" => BlueStrategy::Frequency,
+        "omission" => BlueStrategy::Omission,
+        "markov" => BlueStrategy::Markov,
+        _ => return Err("无效的蓝球策略".to_string()),
+    };
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::recommend_blue(&records, strategy))
+}
+
+#[tauri::command]
+fn cooccurrence_for(state: State<'_, RecordState>, number: u8) -> Result<Vec<(u8, usize)>, String> {
+    validate_number(number, false)?;
+    let records = lock_records(&state)?;
+    Ok(Analyzer::cooccurrence_for(&records, number))
+}
+
+#[tauri::command]
+fn app_healthcheck(settings: State<'_, SettingsState>) -> HealthReport {
+    let mut report = HealthReport {
+        data_dir: None,
+        data_dir_writable: false,
+        data_dir_error: None,
+        csv_exists: false,
+        local_record_count: 0,
+        local_data_error: None,
+        network_reachable: false,
+        network_error: None,
+    };
+
+    match data_manager(&settings) {
+        Ok(manager) => {
+            report.data_dir = Some(manager.data_dir().display().to_string());
+            match manager.check_writable() {
+                Ok(()) => report.data_dir_writable = true,
+                Err(e) => report.data_dir_error = Some(e.to_string()),
+            }
+
+            report.csv_exists = manager.get_csv_path().exists();
+            if report.csv_exists {
+                match manager.load_local_data() {
+                    Ok(records) => report.local_record_count = records.len(),
+                    Err(e) => report.local_data_error = Some(e.to_string()),
+                }
+            }
+        }
+        Err(e) => report.data_dir_error = Some(e),
+    }
+
+    match DataFetcher::probe_source(HEALTHCHECK_TIMEOUT_SECS) {
+        Ok(()) => report.network_reachable = true,
+        Err(e) => report.network_error = Some(e.to_string()),
+    }
+
+    report
+}
+
+#[tauri::command]
+fn compare_recency_backtest(
+    state: State<'_, RecordState>,
+    algorithm: String,
+    test_draws: usize,
+    decay: f64,
+    options: Option<PredictionOptions>,
+) -> Result<RecencyComparison, String> {
+    // 每期要分别跑两种配置，回测期数上限减半
+    if test_draws == 0 || test_draws > MAX_BACKTEST_DRAWS / 2 {
+        return Err(format!("回测期数必须在 1-{} 之间", MAX_BACKTEST_DRAWS / 2));
+    }
+    validate_decay(decay)?;
+
+    let algo_type = parse_algorithm(&algorithm)?;
+    let options = options.unwrap_or_default();
+    validate_options(&options)?;
+    let records = lock_records(&state)?;
+    if records.len() < 2 {
+        return Err("数据不足，无法回测".to_string());
+    }
+
+    Ok(Analyzer::compare_recency_backtest(&records, algo_type, &options, test_draws, decay))
+}
+
+#[tauri::command]
+fn export_analysis_snapshot(
+    state: State<'_, RecordState>,
+    algorithm: String,
+    sections: Option<SnapshotSections>,
+) -> Result<AnalysisSnapshot, String> {
+    let algo_type = parse_algorithm(&algorithm)?;
+    let sections = sections.unwrap_or_default();
+    let records = lock_records(&state)?;
+    Ok(Analyzer::full_snapshot(&records, algo_type, &sections))
+}
+
+#[tauri::command]
+fn fetch_year(settings: State<'_, SettingsState>, year: i32) -> Result<Vec<SsqRecord>, String> {
+    let fetch_config = lock_settings(&settings)?.fetch_config();
+    DataFetcher::fetch_year(year, &fetch_config).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn diff_local_data(
+    state: State<'_, RecordState>,
+    settings: State<'_, SettingsState>,
+) -> Result<DatasetDiff, String> {
+    // 当前数据按保留期数裁剪过，本地 CSV 也要按同样的上限裁剪后再比较，
+    // 否则保留范围之外的旧记录都会显示为差异
+    let manager = data_manager(&settings)?;
+    let local_records = manager.load_local_data().map_err(|e| e.to_string())?;
+    let records = lock_records(&state)?;
+
+    let diff = DataManager::diff(&records, &local_records);
+    println!(
+        "本地数据相对当前数据：新增 {} 期，缺失 {} 期，修正 {} 期",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len()
+    );
+    Ok(diff)
+}
+
+#[tauri::command]
+fn prediction_selection_histogram(
+    state: State<'_, RecordState>,
+    algorithm: String,
+    blue_algorithm: Option<String>,
+    options: Option<PredictionOptions>,
+) -> Result<SelectionReport, String> {
+    let algo_type = parse_algorithm(&algorithm)?;
+    let blue_algo_type = parse_algorithm(blue_algorithm.as_deref().unwrap_or(&algorithm))?;
+    let options = options.unwrap_or_default();
+    validate_options(&options)?;
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::generate_predictions_with_histogram(
+        &records,
+        algo_type,
+        blue_algo_type,
+        &options,
+    ))
+}
+
+#[tauri::command]
+fn check_issue_sequence(state: State<'_, RecordState>) -> Result<IssueSequenceReport, String> {
+    let records = lock_records(&state)?;
+    Ok(DataManager::check_issue_sequence(&records))
+}
+
+#[tauri::command]
+fn balance_ranking(state: State<'_, RecordState>, window: usize) -> Result<Vec<(String, f64)>, String> {
+    if window == 0 {
+        return Err("窗口期数必须大于 0".to_string());
+    }
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::balance_ranking(&records, window))
+}
+
+#[tauri::command]
+fn tickets_for_probability(target_tier: u8, target_prob: f64) -> Result<u64, String> {
+    if !(1..=6).contains(&target_tier) {
+        return Err(format!("中奖等级 {} 无效，应在 1-6 之间", target_tier));
+    }
+    if !target_prob.is_finite() || target_prob <= 0.0 || target_prob >= 1.0 {
+        return Err("目标概率必须在 (0, 1) 之间".to_string());
+    }
+
+    Ok(Analyzer::tickets_for_probability(target_tier, target_prob))
+}
+
+#[tauri::command]
+fn current_streaks(state: State<'_, RecordState>) -> Result<StreakReport, String> {
+    let records = lock_records(&state)?;
+    Ok(Analyzer::current_streaks(&records))
+}
+
+#[tauri::command]
+fn expected_red_matches(state: State<'_, RecordState>, red_balls: Vec<u8>) -> Result<ExpectedMatches, String> {
+    validate_reds(&red_balls)?;
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::expected_matches(&records, &red_balls))
+}
+
+#[tauri::command]
+fn set_record_note(
+    state: State<'_, RecordState>,
+    settings: State<'_, SettingsState>,
+    issue: String,
+    note: Option<String>,
+) -> Result<SsqRecord, String> {
+    let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+
+    let manager = data_manager(&settings)?;
+    let mut local_records = manager.load_all_local_data().map_err(|e| e.to_string())?;
+    let record = local_records
+        .iter_mut()
+        .find(|r| r.issue == issue)
+        .ok_or_else(|| format!("本地数据中没有期号 {}", issue))?;
+    record.note = note.clone();
+    let updated = record.clone();
+    manager.save_local_data(&local_records).map_err(|e| e.to_string())?;
+
+    if let Some(record) = lock_records(&state)?.iter_mut().find(|r| r.issue == issue) {
+        record.note = note;
+    }
+    Ok(updated)
+}
+
+#[tauri::command]
+fn hot_six_backtest(state: State<'_, RecordState>, window: usize) -> Result<HotSixReport, String> {
+    if window == 0 {
+        return Err("统计期数必须大于 0".to_string());
+    }
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::hot_six_backtest(&records, window))
+}
+
+#[tauri::command]
+fn evolve_prediction(
+    state: State<'_, RecordState>,
+    algorithm: String,
+    previous: PredictionResult,
+    change_count: usize,
+) -> Result<PredictionResult, String> {
+    let algo_type = parse_algorithm(&algorithm)?;
+    validate_ticket(&previous.red_balls, previous.blue_ball)?;
+    if !(1..=6).contains(&change_count) {
+        return Err(format!("替换个数 {} 无效，应在 1-6 之间", change_count));
+    }
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::evolve_prediction(&previous, &records, algo_type, change_count))
+}
+
+#[tauri::command]
+fn compare_algorithms(state: State<'_, RecordState>) -> Result<Vec<AlgorithmProfile>, String> {
+    let records = lock_records(&state)?;
+    Ok(Analyzer::compare_algorithms(&records))
+}
+
+#[tauri::command]
+fn check_tickets(
+    state: State<'_, RecordState>,
+    tickets: Vec<(Vec<u8>, u8)>,
+    issue: Option<String>,
+) -> Result<TicketCheck, String> {
+    if tickets.is_empty() {
+        return Err("请至少输入一注号码".to_string());
+    }
+    for (idx, (red_balls, blue_ball)) in tickets.iter().enumerate() {
+        validate_ticket(red_balls, *blue_ball).map_err(|e| format!("第 {} 注: {}", idx + 1, e))?;
+    }
+
+    let records = lock_records(&state)?;
+    let actual = match &issue {
+        Some(issue) => records
+            .iter()
+            .find(|r| &r.issue == issue)
+            .ok_or_else(|| format!("没有期号 {} 的开奖数据", issue))?,
+        None => records.last().ok_or("没有开奖数据")?,
+    };
+
+    let tiers = Analyzer::evaluate_tickets(&tickets, actual);
+    let total_winnings = tiers.iter().flatten().map(|&tier| Analyzer::prize_amount(tier)).sum();
+    Ok(TicketCheck {
+        issue: actual.issue.clone(),
+        tiers,
+        total_winnings,
+    })
+}
+
+#[tauri::command]
+fn blue_by_sum_parity(
+    state: State<'_, RecordState>,
+    algorithm: String,
+) -> Result<(Vec<BallFrequency>, Vec<BallFrequency>), String> {
+    let algo_type = parse_algorithm(&algorithm)?;
+    let records = lock_records(&state)?;
+    Ok(Analyzer::blue_by_sum_parity(&records, algo_type))
+}
+
+#[tauri::command]
+fn quick_pick(
+    state: State<'_, RecordState>,
+    algorithm: String,
+    seed: Option<u64>,
+) -> Result<PredictionResult, String> {
+    let algo_type = parse_algorithm(&algorithm)?;
+    let records = lock_records(&state)?;
+    Ok(Analyzer::quick_pick(&records, algo_type, seed))
+}
+
+#[tauri::command]
+fn grade_predictions(predictions: Vec<PredictionResult>, actual: SsqRecord) -> Result<GradeReport, String> {
+    validate_ticket(&actual.red_balls(), actual.blue_ball).map_err(|e| format!("开奖号码无效: {}", e))?;
+    for (idx, prediction) in predictions.iter().enumerate() {
+        validate_ticket(&prediction.red_balls, prediction.blue_ball)
+            .map_err(|e| format!("第 {} 注: {}", idx + 1, e))?;
+    }
+
+    let grades = Analyzer::grade_predictions(&predictions, &actual);
+    let best = Analyzer::best_grade(&grades);
+    Ok(GradeReport {
+        issue: actual.issue,
+        grades,
+        best,
+    })
+}
+
+#[tauri::command]
+fn recent_presence_matrix(state: State<'_, RecordState>, window: usize) -> Result<Vec<(u8, Vec<bool>)>, String> {
+    if window == 0 {
+        return Err("统计期数必须大于 0".to_string());
+    }
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::recent_presence_matrix(&records, window))
+}
+
+#[tauri::command]
+fn co_overdue_pairs(
+    state: State<'_, RecordState>,
+    min_omission: Option<usize>,
+) -> Result<Vec<(RedPair, usize)>, String> {
+    let min_omission = min_omission.unwrap_or(CO_OVERDUE_MIN_OMISSION);
+    if min_omission == 0 {
+        return Err("最小遗漏期数必须大于 0".to_string());
+    }
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::co_overdue_pairs(&records, min_omission))
+}
+
+#[tauri::command]
+fn fairness_deviation(state: State<'_, RecordState>) -> Result<FairnessReport, String> {
+    let records = lock_records(&state)?;
+    Ok(Analyzer::fairness_deviation(&records))
+}
+
+#[tauri::command]
+fn lucky_dip(count: usize, seed: Option<u64>) -> Result<Vec<PredictionResult>, String> {
+    if !(1..=MAX_LUCKY_DIP_TICKETS).contains(&count) {
+        return Err(format!("机选注数必须在 1-{} 之间", MAX_LUCKY_DIP_TICKETS));
+    }
+
+    Ok(Analyzer::random_tickets(count, seed))
+}
+
+#[tauri::command]
+fn merge_csv_files(path_a: String, path_b: String, out_path: String) -> Result<MergeReport, String> {
+    DataManager::merge_files(
+        std::path::Path::new(&path_a),
+        std::path::Path::new(&path_b),
+        std::path::Path::new(&out_path),
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn top_red_triplets(state: State<'_, RecordState>, top_n: usize) -> Result<Vec<([u8; 3], usize)>, String> {
+    if !(1..=MAX_TOP_TRIPLETS).contains(&top_n) {
+        return Err(format!("返回个数必须在 1-{} 之间", MAX_TOP_TRIPLETS));
+    }
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::top_red_triplets(&records, top_n))
+}
+
+#[tauri::command]
+fn omission_pressure(state: State<'_, RecordState>, is_blue: bool) -> Result<Vec<(u8, f64)>, String> {
+    let records = lock_records(&state)?;
+    Ok(Analyzer::omission_pressure(&records, is_blue))
+}
+
+#[tauri::command]
+fn bootstrap_intervals(
+    state: State<'_, RecordState>,
+    algorithm: String,
+    is_blue: bool,
+    iterations: Option<usize>,
+    seed: Option<u64>,
+) -> Result<Vec<WeightInterval>, String> {
+    let algo_type = parse_algorithm(&algorithm)?;
+    let iterations = iterations.unwrap_or(DEFAULT_BOOTSTRAP_ITERATIONS);
+    if !(1..=MAX_BOOTSTRAP_ITERATIONS).contains(&iterations) {
+        return Err(format!("重采样次数必须在 1-{} 之间", MAX_BOOTSTRAP_ITERATIONS));
+    }
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::bootstrap_weights(&records, algo_type, is_blue, iterations, seed))
+}
+
+#[tauri::command]
+fn longest_absence(
+    state: State<'_, RecordState>,
+    number: u8,
+    is_blue: bool,
+) -> Result<AbsenceRun, String> {
+    validate_number(number, is_blue)?;
+    let records = lock_records(&state)?;
+    Ok(Analyzer::longest_absence(&records, number, is_blue))
+}
+
+/// 执行批量分析中的一项
+fn run_analysis(
+    records: &[SsqRecord],
+    request: AnalysisRequest,
+) -> Result<AnalysisResponse, String> {
+    let response = match request {
+        AnalysisRequest::Frequency { algorithm, is_blue } => {
+            let algorithm = parse_algorithm(&algorithm)?;
+            AnalysisResponse::Frequency(if is_blue {
+                Analyzer::analyze_blue_frequency(records, algorithm)
+            } else {
+                Analyzer::analyze_red_frequency(records, algorithm)
+            })
+        }
+        AnalysisRequest::Omission { is_blue } => {
+            AnalysisResponse::Omission(Analyzer::omission_stats(records, is_blue))
+        }
+        AnalysisRequest::OddEven => {
+            AnalysisResponse::OddEven(Analyzer::analyze_odd_even_distribution(records))
+        }
+        AnalysisRequest::Zone => {
+            AnalysisResponse::Zone(Analyzer::analyze_zone_distribution(records))
+        }
+        AnalysisRequest::Decade => {
+            AnalysisResponse::Decade(Analyzer::analyze_decade_distribution(records))
+        }
+        AnalysisRequest::Consecutive => {
+            AnalysisResponse::Consecutive(Analyzer::analyze_consecutive_distribution(records))
+        }
+        AnalysisRequest::ConsecutiveRuns => AnalysisResponse::ConsecutiveRuns(
+            Analyzer::consecutive_run_length_distribution(records),
+        ),
+        AnalysisRequest::Sum => AnalysisResponse::Sum(Analyzer::analyze_sum_distribution(records)),
+        AnalysisRequest::Span => {
+            AnalysisResponse::Span(Analyzer::analyze_span_distribution(records))
+        }
+        AnalysisRequest::Tail => {
+            AnalysisResponse::Tail(Analyzer::analyze_tail_distribution(records))
+        }
+        AnalysisRequest::Streaks => AnalysisResponse::Streaks(Analyzer::current_streaks(records)),
+        AnalysisRequest::Fairness => {
+            AnalysisResponse::Fairness(Analyzer::fairness_deviation(records))
+        }
+        AnalysisRequest::OmissionPressure { is_blue } => {
+            AnalysisResponse::OmissionPressure(Analyzer::omission_pressure(records, is_blue))
+        }
+        AnalysisRequest::LongestAbsence { number, is_blue } => {
+            validate_number(number, is_blue)?;
+            AnalysisResponse::LongestAbsence(Analyzer::longest_absence(records, number, is_blue))
+        }
+    };
+    Ok(response)
+}
+
+#[tauri::command]
+fn batch_analyze(
+    state: State<'_, RecordState>,
+    requests: Vec<serde_json::Value>,
+) -> Result<Vec<AnalysisResponse>, String> {
+    let records = lock_records(&state)?;
+    Ok(requests.into_iter().map(|value| analyze_value(&records, value)).collect())
+}
+
+/// 解析并执行批量分析中的一项，无法识别的请求只让该项返回错误
+fn analyze_value(records: &[SsqRecord], value: serde_json::Value) -> AnalysisResponse {
+    serde_json::from_value::<AnalysisRequest>(value)
+        .map_err(|e| format!("无法识别的分析请求: {}", e))
+        .and_then(|request| run_analysis(records, request))
+        .unwrap_or_else(AnalysisResponse::Error)
+}
+
+#[tauri::command]
+fn simulate_play(
+    state: State<'_, RecordState>,
+    predictions: Vec<PredictionResult>,
+    draws: usize,
+    seed: Option<u64>,
+) -> Result<PlaySimulation, String> {
+    if predictions.is_empty() {
+        return Err("请至少输入一注号码".to_string());
+    }
+    if !(1..=MAX_SIMULATED_DRAWS).contains(&draws) {
+        return Err(format!("模拟期数必须在 1-{} 之间", MAX_SIMULATED_DRAWS));
+    }
+    for (idx, prediction) in predictions.iter().enumerate() {
+        validate_ticket(&prediction.red_balls, prediction.blue_ball)
+            .map_err(|e| format!("第 {} 注: {}", idx + 1, e))?;
+    }
+
+    let records = lock_records(&state)?;
+    if records.is_empty() {
+        return Err("没有开奖数据".to_string());
+    }
+    Ok(Analyzer::simulate_play(&predictions, &records, draws, seed))
+}
+
+#[tauri::command]
+fn frequency_by_weekday(
+    state: State<'_, RecordState>,
+    algorithm: String,
+) -> Result<Vec<WeekdayFrequency>, String> {
+    let algo_type = parse_algorithm(&algorithm)?;
+    let records = lock_records(&state)?;
+    Ok(Analyzer::frequency_by_weekday(&records, algo_type))
+}
+
+#[tauri::command]
+fn dataset_fingerprint(state: State<'_, RecordState>) -> Result<DatasetFingerprint, String> {
+    let records = lock_records(&state)?;
+    Ok(DataManager::dataset_fingerprint(&records))
+}
+
+#[tauri::command]
+fn detect_anomalies(
+    state: State<'_, RecordState>,
+    settings: State<'_, SettingsState>,
+) -> Result<Vec<AnomalyReport>, String> {
+    let today = lock_settings(&settings)?.today();
+    let records = lock_records(&state)?;
+    Ok(Analyzer::detect_anomalies(&records, today))
+}
+
+#[tauri::command]
+fn wheel_tickets(pool: Vec<u8>, blue_ball: u8, guarantee: Option<u8>) -> Result<Vec<PredictionResult>, String> {
+    if !WHEEL_POOL_SIZES.contains(&pool.len()) {
+        return Err(format!(
+            "号码池必须有 {}-{} 个红球，实际 {} 个",
+            WHEEL_POOL_SIZES.start(),
+            WHEEL_POOL_SIZES.end(),
+            pool.len()
+        ));
+    }
+    for (idx, &ball) in pool.iter().enumerate() {
+        validate_number(ball, false)?;
+        if pool[..idx].contains(&ball) {
+            return Err(format!("号码池中有重复号码: {}", ball));
+        }
+    }
+    validate_number(blue_ball, true)?;
+    if let Some(g) = guarantee {
+        if !(1..=6).contains(&g) {
+            return Err("保证命中个数必须在 1-6 之间".to_string());
+        }
+    }
+
+    Ok(Analyzer::wheel(&pool, blue_ball, guarantee))
+}
+
+#[tauri::command]
+fn ticket_set_coverage(tickets: Vec<(Vec<u8>, u8)>) -> Result<CoverageReport, String> {
+    if tickets.is_empty() {
+        return Err("请至少输入一注号码".to_string());
+    }
+    for (idx, (red_balls, blue_ball)) in tickets.iter().enumerate() {
+        validate_ticket(red_balls, *blue_ball).map_err(|e| format!("第 {} 注: {}", idx + 1, e))?;
+    }
+
+    Ok(Analyzer::ticket_set_coverage(&tickets))
+}
+
+#[tauri::command]
+fn blue_rolling_frequency(
+    state: State<'_, RecordState>,
+    blue_number: u8,
+    window: usize,
+) -> Result<Vec<(String, f64)>, String> {
+    validate_number(blue_number, true)?;
+    if window == 0 {
+        return Err("统计期数必须大于 0".to_string());
+    }
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::blue_rolling_frequency(&records, blue_number, window))
+}
+
+#[tauri::command]
+fn reds_given_prev_blue(state: State<'_, RecordState>, prev_blue: u8) -> Result<Vec<BallFrequency>, String> {
+    validate_number(prev_blue, true)?;
+    let records = lock_records(&state)?;
+    Ok(Analyzer::reds_given_prev_blue(&records, prev_blue))
+}
+
+#[tauri::command]
+fn number_temperature(
+    state: State<'_, RecordState>,
+    is_blue: bool,
+    weights: Option<TemperatureWeights>,
+) -> Result<Vec<(u8, f64)>, String> {
+    let weights = weights.unwrap_or_default();
+    let components = [weights.frequency, weights.omission, weights.trend];
+    if components.iter().any(|w| !w.is_finite() || *w < 0.0) {
+        return Err("温度权重必须是非负数".to_string());
+    }
+    if components.iter().sum::<f64>() <= 0.0 {
+        return Err("温度权重不能全为 0".to_string());
+    }
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::number_temperature(&records, is_blue, &weights))
+}
+
+#[tauri::command]
+fn consecutive_run_lengths(state: State<'_, RecordState>) -> Result<Vec<(u8, usize)>, String> {
+    let records = lock_records(&state)?;
+    Ok(Analyzer::consecutive_run_length_distribution(&records))
+}
+
+#[tauri::command]
+fn consecutive_repeat_distribution(
+    state: State<'_, RecordState>,
+) -> Result<Vec<(usize, usize)>, String> {
+    let records = lock_records(&state)?;
+    Ok(Analyzer::consecutive_repeat_distribution(&records))
+}
+
+#[tauri::command]
+fn blue_sum_relationship(state: State<'_, RecordState>) -> Result<RelationshipModel, String> {
+    let records = lock_records(&state)?;
+    Ok(Analyzer::blue_sum_relationship(&records))
+}
+
+#[tauri::command]
+fn reparse_cached(
+    settings: State<'_, SettingsState>,
+    file: Option<String>,
+) -> Result<ReparseReport, String> {
+    let manager = data_manager(&settings)?;
+    let report = manager
+        .reparse_cached(file.as_deref(), MAX_RECORDS)
+        .map_err(|e| e.to_string())?;
+    println!("重新解析 {}：得到 {} 条记录", report.file, report.records.len());
+    Ok(report)
+}
+
+#[tauri::command]
+fn decade_distribution(state: State<'_, RecordState>) -> Result<Vec<(String, usize)>, String> {
+    let records = lock_records(&state)?;
+    Ok(Analyzer::analyze_decade_distribution(&records))
+}
+
+#[tauri::command]
+fn due_forecast(state: State<'_, RecordState>, number: u8, is_blue: bool) -> Result<DueForecast, String> {
+    validate_number(number, is_blue)?;
+    let records = lock_records(&state)?;
+    Ok(Analyzer::due_forecast(&records, number, is_blue))
+}
+
+#[tauri::command]
+fn historical_bankroll(
+    state: State<'_, RecordState>,
+    algorithm: String,
+    tickets_per_draw: usize,
+    entropy: Option<String>,
+) -> Result<BankrollReport, String> {
+    let algo_type = parse_algorithm(&algorithm)?;
+    if !(1..=PREDICTION_COUNT).contains(&tickets_per_draw) {
+        return Err(format!("每期注数必须在 1-{} 之间", PREDICTION_COUNT));
+    }
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::historical_bankroll(
+        &records,
+        algo_type,
+        tickets_per_draw,
+        entropy.as_deref(),
+    ))
+}
+
+#[tauri::command]
+fn companionship_graph(
+    state: State<'_, RecordState>,
+    min_cooccurrence: usize,
+) -> Result<Vec<(u8, u8, usize)>, String> {
+    if min_cooccurrence == 0 {
+        return Err("最小同现次数必须大于 0".to_string());
+    }
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::companionship_graph(&records, min_cooccurrence))
+}
+
+#[tauri::command]
+fn red_frequency_by_issue_range(
+    state: State<'_, RecordState>,
+    start_issue: String,
+    end_issue: String,
+    algorithm: String,
+) -> Result<Vec<BallFrequency>, String> {
+    let algo_type = parse_algorithm(&algorithm)?;
+    let start = IssueNumber::parse(&start_issue).ok_or_else(|| format!("无效的期号: {}", start_issue))?;
+    let end = IssueNumber::parse(&end_issue).ok_or_else(|| format!("无效的期号: {}", end_issue))?;
+    if start > end {
+        return Err(format!("起始期号 {} 不能晚于结束期号 {}", start_issue, end_issue));
+    }
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::analyze_red_frequency_by_issue_range(&records, start, end, algo_type))
+}
+
+#[tauri::command]
+fn latest_draw_highlights(state: State<'_, RecordState>) -> Result<Vec<String>, String> {
+    let records = lock_records(&state)?;
+    Ok(Analyzer::latest_draw_highlights(&records))
+}
+
+#[tauri::command]
+fn generate_fair_predictions(
+    state: State<'_, RecordState>,
+    algorithm: String,
+    blue_algorithm: Option<String>,
+    entropy: String,
+    options: Option<PredictionOptions>,
+) -> Result<FairPredictions, String> {
+    let algo_type = parse_algorithm(&algorithm)?;
+    let blue_algo_type = parse_algorithm(blue_algorithm.as_deref().unwrap_or(&algorithm))?;
+    let options = options.unwrap_or_default();
+    validate_options(&options)?;
+    validate_entropy(&entropy, &options)?;
+
+    let records = lock_records(&state)?;
+    Ok(Analyzer::fair_predictions(&records, algo_type, blue_algo_type, &options, &entropy))
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .manage(RecordState::default())
+        .manage(SettingsState::default())
         .invoke_handler(tauri::generate_handler![
             load_and_update_data,
+            refresh_records,
             analyze_frequency,
-            generate_predictions
+            generate_predictions,
+            next_draw_date,
+            absent_numbers,
+            red_correlations,
+            export_ticket_slip,
+            debug_fetch,
+            rolling_hot_set,
+            best_case_against_history,
+            import_csv,
+            number_stat_card,
+            backtest,
+            sum_tail_distribution,
+            filter_draws,
+            rank_combination,
+            recommend_blue,
+            cooccurrence_for,
+            app_healthcheck,
+            compare_recency_backtest,
+            export_analysis_snapshot,
+            fetch_year,
+            diff_local_data,
+            prediction_selection_histogram,
+            check_issue_sequence,
+            balance_ranking,
+            tickets_for_probability,
+            current_streaks,
+            expected_red_matches,
+            set_record_note,
+            hot_six_backtest,
+            evolve_prediction,
+            compare_algorithms,
+            check_tickets,
+            blue_by_sum_parity,
+            quick_pick,
+            grade_predictions,
+            recent_presence_matrix,
+            co_overdue_pairs,
+            fairness_deviation,
+            lucky_dip,
+            merge_csv_files,
+            top_red_triplets,
+            omission_pressure,
+            bootstrap_intervals,
+            longest_absence,
+            batch_analyze,
+            simulate_play,
+            frequency_by_weekday,
+            dataset_fingerprint,
+            detect_anomalies,
+            wheel_tickets,
+            ticket_set_coverage,
+            blue_rolling_frequency,
+            reds_given_prev_blue,
+            number_temperature,
+            consecutive_run_lengths,
+            consecutive_repeat_distribution,
+            blue_sum_relationship,
+            reparse_cached,
+            decade_distribution,
+            due_forecast,
+            historical_bankroll,
+            companionship_graph,
+            red_frequency_by_issue_range,
+            latest_draw_highlights,
+            generate_fair_predictions
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blue_pool_cannot_be_combined_with_a_forced_blue() {
+        let pool = PredictionOptions {
+            blue_pool: Some(vec![1, 8, 16]),
+            ..Default::default()
+        };
+        assert!(validate_options(&pool).is_ok());
+
+        for options in [
+            PredictionOptions { include_last_blue: true, ..pool.clone() },
+            PredictionOptions { blue_from_sum: true, ..pool.clone() },
+            PredictionOptions { blue_pool: Some(Vec::new()), ..Default::default() },
+            PredictionOptions { blue_pool: Some(vec![3, 3]), ..Default::default() },
+            PredictionOptions { blue_pool: Some(vec![17]), ..Default::default() },
+        ] {
+            assert!(validate_options(&options).is_err(), "{:?}", options);
+        }
+    }
+
+    #[test]
+    fn batch_frequency_accepts_the_same_algorithm_names_as_other_commands() {
+        let records = vec![SsqRecord::new(
+            "24001".to_string(),
+            "2024-01-02".to_string(),
+            vec![1, 5, 12, 18, 25, 33],
+            7,
+        )];
+        let request = |algorithm: &str| serde_json::json!({ "analysis": "frequency", "algorithm": algorithm, "is_blue": true });
+
+        for algorithm in ["hot", "cold", "realistic"] {
+            match analyze_value(&records, request(algorithm)) {
+                AnalysisResponse::Frequency(frequencies) => assert_eq!(frequencies.len(), 16),
+                other => panic!("{}: {:?}", algorithm, other),
+            }
+        }
+        // 枚举名不是对外的算法名称，只让该项返回错误
+        assert!(matches!(
+            analyze_value(&records, request("HotStaysHot")),
+            AnalysisResponse::Error(_)
+        ));
+    }
+
+    #[test]
+    fn blue_window_is_independent_of_the_red_window() {
+        // 前 20 期蓝球是 1，最近 5 期是 2
+        let records: Vec<SsqRecord> = (0..25)
+            .map(|i| {
+                let blue = if i < 20 { 1 } else { 2 };
+                let reds = vec![1, 5, 12, 18, 25, 33];
+                SsqRecord::new(
+                    format!("24{:03}", i + 1),
+                    "2024-01-02".to_string(),
+                    reds,
+                    blue,
+                )
+            })
+            .collect();
+        let count = |frequencies: &[BallFrequency], number: u8| {
+            frequencies
+                .iter()
+                .find(|f| f.number == number)
+                .unwrap()
+                .frequency
+        };
+
+        let (red, blue) =
+            windowed_frequencies(&records, AlgorithmType::HotStaysHot, None, Some(5)).unwrap();
+        assert_eq!(count(&red, 1), 25);
+        assert_eq!((count(&blue, 1), count(&blue, 2)), (0, 5));
+
+        let (red, blue) =
+            windowed_frequencies(&records, AlgorithmType::HotStaysHot, Some(10), None).unwrap();
+        assert_eq!(count(&red, 1), 10);
+        assert_eq!((count(&blue, 1), count(&blue, 2)), (20, 5));
+
+        assert!(windowed_frequencies(&records, AlgorithmType::HotStaysHot, None, Some(0)).is_err());
+    }
+
+    #[test]
+    fn entropy_must_be_non_empty_and_without_a_time_budget() {
+        let options = PredictionOptions::default();
+        assert!(validate_entropy("承诺", &options).is_ok());
+        assert!(validate_entropy("", &options).is_err());
+
+        let budgeted = PredictionOptions {
+            max_duration_ms: Some(100),
+            ..Default::default()
+        };
+        assert!(validate_entropy("承诺", &budgeted).is_err());
+        // 通过选项传入熵时同样校验
+        let seeded = PredictionOptions {
+            entropy: Some("承诺".to_string()),
+            ..budgeted
+        };
+        assert!(validate_options(&seeded).is_err());
+    }
+}
@@ -1,11 +1,16 @@
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::ser::SerializeStruct;
 use serde::de::{self, MapAccess, Visitor};
-use chrono::NaiveDate;
+use chrono::{NaiveDate, Weekday};
 use std::fmt;
 
 /// 双色球历史记录
-#[derive(Debug, Clone)]
+///
+/// 红球顺序约定：网络数据按从小到大保存；导入 CSV 时默认同样排序，
+/// 指定保留开奖顺序时按文件中的顺序（出球顺序）原样保存。
+/// 因此 `red_balls()` 不保证有序，需要有序时使用 `sorted_red_balls()`。
+/// 旧版本地 CSV 在迁移时已统一排序，不含出球顺序，无法用于按位置分析。
+#[derive(Debug, Clone, PartialEq)]
 pub struct SsqRecord {
     /// 期号
     pub issue: String,
@@ -25,6 +30,10 @@ pub struct SsqRecord {
     pub red6: u8,
     /// 蓝球
     pub blue_ball: u8,
+    /// 备注，如"数据存疑"，没有备注时为 `None`
+    pub note: Option<String>,
+    /// 是否为生成的示例数据（非真实开奖）
+    pub synthetic: bool,
 }
 
 // 自定义序列化，为 JSON 添加 red_balls 数组
@@ -33,11 +42,13 @@ impl Serialize for SsqRecord {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("SsqRecord", 4)?;
+        let mut state = serializer.serialize_struct("SsqRecord", 6)?;
         state.serialize_field("issue", &self.issue)?;
         state.serialize_field("date", &self.date)?;
         state.serialize_field("red_balls", &self.red_balls())?;
         state.serialize_field("blue_ball", &self.blue_ball)?;
+        state.serialize_field("note", &self.note)?;
+        state.serialize_field("synthetic", &self.synthetic)?;
         state.end()
     }
 }
@@ -61,6 +72,8 @@ impl<'de> Deserialize<'de> for SsqRecord {
             Red5,
             Red6,
             BlueBall,
+            Note,
+            Synthetic,
         }
 
         struct SsqRecordVisitor;
@@ -86,6 +99,8 @@ impl<'de> Deserialize<'de> for SsqRecord {
                 let mut red5 = None;
                 let mut red6 = None;
                 let mut blue_ball = None;
+                let mut note: Option<String> = None;
+                let mut synthetic: Option<bool> = None;
 
                 while let Some(key) = map.next_key()? {
                     match key {
@@ -119,6 +134,12 @@ impl<'de> Deserialize<'de> for SsqRecord {
                         Field::BlueBall => {
                             blue_ball = Some(map.next_value()?);
                         }
+                        Field::Note => {
+                            note = map.next_value()?;
+                        }
+                        Field::Synthetic => {
+                            synthetic = map.next_value()?;
+                        }
                     }
                 }
 
@@ -153,11 +174,15 @@ impl<'de> Deserialize<'de> for SsqRecord {
                     red5: r5,
                     red6: r6,
                     blue_ball,
+                    // 旧文件没有备注列，CSV 中的空备注也视为没有备注
+                    note: note.filter(|n| !n.is_empty()),
+                    // CSV 中真实数据的该列为空
+                    synthetic: synthetic.unwrap_or(false),
                 })
             }
         }
 
-        const FIELDS: &[&str] = &["issue", "date", "red_balls", "red1", "red2", "red3", "red4", "red5", "red6", "blue_ball"];
+        const FIELDS: &[&str] = &["issue", "date", "red_balls", "red1", "red2", "red3", "red4", "red5", "red6", "blue_ball", "note", "synthetic"];
         deserializer.deserialize_struct("SsqRecord", FIELDS, SsqRecordVisitor)
     }
 }
@@ -175,6 +200,8 @@ impl SsqRecord {
             red5: red_balls[4],
             red6: red_balls[5],
             blue_ball,
+            note: None,
+            synthetic: false,
         }
     }
 
@@ -185,8 +212,72 @@ impl SsqRecord {
     pub fn red_balls(&self) -> Vec<u8> {
         vec![self.red1, self.red2, self.red3, self.red4, self.red5, self.red6]
     }
+
+    /// 开奖内容（日期、号码）是否相同，不比较备注；红球只比较号码，不比较出球顺序
+    pub fn same_draw(&self, other: &SsqRecord) -> bool {
+        self.issue == other.issue
+            && self.date == other.date
+            && self.sorted_red_balls() == other.sorted_red_balls()
+            && self.blue_ball == other.blue_ball
+    }
+
+    pub fn sorted_red_balls(&self) -> Vec<u8> {
+        let mut reds = self.red_balls();
+        reds.sort();
+        reds
+    }
+
+    /// 将红球按从小到大重新排列
+    pub fn sort_red_balls(&mut self) {
+        let reds = self.sorted_red_balls();
+        [self.red1, self.red2, self.red3, self.red4, self.red5, self.red6] =
+            [reds[0], reds[1], reds[2], reds[3], reds[4], reds[5]];
+    }
+
+    pub fn issue_number(&self) -> Option<IssueNumber> {
+        IssueNumber::parse(&self.issue)
+    }
+}
+
+/// 拆分后的期号：年份 + 当年序号
+///
+/// 期号有 `24001`（两位年份）和 `2024001`（四位年份）两种写法，
+/// 序号每年从 001 重新开始，按 (年份, 序号) 比较才能得到正确的时间顺序。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IssueNumber {
+    pub year: i32,
+    pub seq: u32,
+}
+
+impl IssueNumber {
+    pub fn parse(issue: &str) -> Option<Self> {
+        if !issue.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let (year, seq) = match issue.len() {
+            5 => (2000 + issue[..2].parse::<i32>().ok()?, issue[2..].parse().ok()?),
+            7 => (issue[..4].parse().ok()?, issue[4..].parse().ok()?),
+            _ => return None,
+        };
+        (seq > 0).then_some(Self { year, seq })
+    }
+
+    /// 从 `self` 到 `next` 之间缺失的期数
+    ///
+    /// 同一年内按序号相减；跨年时上一年的期数未知，
+    /// 只要下一年从第 1 期开始就视为连续，否则缺失下一年序号之前的期数。
+    pub fn missing_before(&self, next: &IssueNumber) -> u32 {
+        if next.year == self.year {
+            next.seq.saturating_sub(self.seq + 1)
+        } else {
+            next.seq - 1
+        }
+    }
 }
 
+/// 红球号码对 (小号, 大号)
+pub type RedPair = (u8, u8);
+
 /// 球号频率统计
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BallFrequency {
@@ -202,6 +293,18 @@ pub enum AlgorithmType {
     HotStaysHot,
     /// 冷号反弹
     ColdBounceBack,
+    /// 贴近真实开奖：号码权重取历史出现率，并按历史上（和值分段, 三区比）的联合分布
+    /// 抽取目标后生成同时符合两者的红球组合
+    Realistic,
+}
+
+impl AlgorithmType {
+    /// 全部算法，按界面中的顺序排列
+    pub const ALL: [AlgorithmType; 3] = [
+        AlgorithmType::HotStaysHot,
+        AlgorithmType::ColdBounceBack,
+        AlgorithmType::Realistic,
+    ];
 }
 
 /// 预测结果
@@ -214,3 +317,914 @@ pub struct PredictionResult {
     /// 得分（置信度）
     pub score: f64,
 }
+
+/// 预测生成选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PredictionOptions {
+    /// 排序后相邻红球的最小间距（1 即普通的不重复约束）
+    pub min_gap: u8,
+    /// 沿用上一期蓝球：直接使用最新一期的蓝球而不再按权重抽取，
+    /// 优先于蓝球的任何权重调整
+    pub include_last_blue: bool,
+    /// softmax 温度：按 `exp(weight / T)` 变换权重后再抽取。
+    /// T 趋于 0 时接近总是选权重最高的号码，T 趋于无穷时接近均匀随机；
+    /// `None` 表示直接使用原始权重
+    pub temperature: Option<f64>,
+    /// 排除与历史开奖完全相同（红球 + 蓝球）的组合
+    pub exclude_historical: bool,
+    /// 时间衰减系数（0 < decay <= 1）：越早的开奖在频率统计中的权重越低，
+    /// `None` 表示所有历史同等对待
+    pub recency_decay: Option<f64>,
+    /// 和值/跨度软惩罚：得分乘以以典型和值、跨度为中心的高斯因子，
+    /// 偏离越远得分越低但不会被排除；`None` 表示不启用
+    pub shape_penalty: Option<ShapePenalty>,
+    /// 只从这些蓝球中按权重抽取（权重在集合内归一化），`None` 表示不限制；
+    /// 不能与沿用上一期蓝球同时使用
+    pub blue_pool: Option<Vec<u8>>,
+    /// 三区红球个数，如 `[2, 2, 2]`，设置后每个区内按权重抽取对应个数，`None` 表示不限制
+    pub zone_ratio: Option<[u8; 3]>,
+    /// 反向策略：偏好与最近几期开奖重复较少的组合，`None` 表示不启用
+    pub distinctness: Option<Distinctness>,
+    /// 对频率权重的信任程度（0-1）：每个号码的权重取 `belief × 原权重 + (1 - belief) × 平均权重`，
+    /// 得分也由混合后的权重计算。1 即完全按频率，0 时所有号码权重相同，
+    /// 前 10 注实际上就是随机的合法号码（每注中奖概率本来就相同）；`None` 等同于 1
+    pub belief: Option<f64>,
+    /// 蓝球直接取 `红球和值 % 16 + 1`（历史上几乎没有这种关系，仅供探索）；
+    /// 沿用上一期蓝球时不生效
+    pub blue_from_sum: bool,
+    /// 去重方式：默认红球和蓝球都相同才算重复，`RedOnly` 时红球相同即算重复
+    pub dedup_by: DedupMode,
+    /// 迭代的时间预算（毫秒）：超时后停止迭代，直接用已有结果排序去重；
+    /// 至少会完成一定次数的迭代，`None` 表示始终完成全部迭代
+    pub max_duration_ms: Option<u64>,
+    /// 公平性演示用的熵字符串：设置后以其 SHA-256 作为种子驱动 ChaCha20 随机数生成器，
+    /// 相同的字符串总是得到相同的预测；`None` 表示使用系统随机数
+    pub entropy: Option<String>,
+}
+
+/// 和值/跨度高斯惩罚的参数，未指定的中心和宽度取历史数据的均值和标准差
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShapePenalty {
+    pub sum_center: Option<f64>,
+    pub sum_width: Option<f64>,
+    pub span_center: Option<f64>,
+    pub span_width: Option<f64>,
+}
+
+/// 预测结果的去重方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum DedupMode {
+    /// 红球和蓝球都相同才算重复
+    #[default]
+    RedAndBlue,
+    /// 只比较红球，保证每注的红球组合都不同
+    RedOnly,
+}
+
+/// 与近期开奖的差异度参数：得分乘以 `(1 - weight) + weight × 差异度`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Distinctness {
+    /// 与最近多少期开奖比较
+    pub recent_draws: usize,
+    /// 差异度在得分中的占比（0-1）
+    pub weight: f64,
+}
+
+impl Default for PredictionOptions {
+    fn default() -> Self {
+        Self {
+            min_gap: 1,
+            include_last_blue: false,
+            temperature: None,
+            exclude_historical: false,
+            recency_decay: None,
+            shape_penalty: None,
+            blue_pool: None,
+            zone_ratio: None,
+            distinctness: None,
+            belief: None,
+            blue_from_sum: false,
+            dedup_by: DedupMode::default(),
+            max_duration_ms: None,
+            entropy: None,
+        }
+    }
+}
+
+/// 单个选择器的解析统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SelectorAttempt {
+    /// CSS 选择器
+    pub selector: String,
+    /// 扫描的行数
+    pub rows_scanned: usize,
+    /// 列数不足被跳过的行数
+    pub too_few_columns: usize,
+    /// 期号无效被跳过的行数
+    pub invalid_issue: usize,
+    /// 红球解析失败的行数
+    pub red_parse_failed: usize,
+    /// 红球有重复的行数
+    pub duplicate_reds: usize,
+    /// 蓝球解析失败的行数
+    pub blue_parse_failed: usize,
+    /// 日期解析失败的行数
+    pub date_parse_failed: usize,
+    /// 成功解析的行数
+    pub parsed: usize,
+    /// 期号、号码或日期无效而被跳过的行（列数不足的表头等行不计入）
+    pub failures: Vec<RowFailure>,
+}
+
+/// 解析失败的单行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowFailure {
+    /// 行号（从 0 开始）
+    pub row: usize,
+    /// 失败原因，包含原始内容
+    pub reason: String,
+}
+
+/// 网页解析诊断信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParseDiagnostics {
+    /// 最终成功解析出数据的选择器
+    pub selector: Option<String>,
+    /// 依次尝试的选择器及其统计
+    pub attempts: Vec<SelectorAttempt>,
+}
+
+impl ParseDiagnostics {
+    /// 生成一行可读的诊断摘要
+    pub fn summary(&self) -> String {
+        let attempts = self
+            .attempts
+            .iter()
+            .map(|a| {
+                format!(
+                    "'{}': 扫描 {} 行, 列数不足 {}, 期号无效 {}, 红球失败 {}, 红球重复 {}, 蓝球失败 {}, 日期失败 {}, 成功 {}",
+                    a.selector,
+                    a.rows_scanned,
+                    a.too_few_columns,
+                    a.invalid_issue,
+                    a.red_parse_failed,
+                    a.duplicate_reds,
+                    a.blue_parse_failed,
+                    a.date_parse_failed,
+                    a.parsed
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        match &self.selector {
+            Some(selector) => format!("使用选择器 '{}' [{}]", selector, attempts),
+            None => format!("所有选择器均未解析到数据 [{}]", attempts),
+        }
+    }
+
+    /// 最终使用的选择器下解析失败的行
+    pub fn failures(&self) -> &[RowFailure] {
+        self.selector
+            .as_ref()
+            .and_then(|selector| self.attempts.iter().find(|a| &a.selector == selector))
+            .map(|a| a.failures.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+/// 号码走势
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum NumberTrend {
+    /// 上升
+    Rising,
+    /// 平稳
+    Stable,
+    /// 下降
+    Falling,
+}
+
+/// 号码冷热分类
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum NumberCategory {
+    /// 热号
+    Hot,
+    /// 温号
+    Warm,
+    /// 冷号
+    Cold,
+}
+
+/// 单个号码的综合统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NumberStatCard {
+    pub number: u8,
+    pub is_blue: bool,
+    /// 出现次数
+    pub frequency: usize,
+    /// 当前遗漏
+    pub current_omission: usize,
+    /// 最大遗漏
+    pub max_omission: usize,
+    /// 平均间隔，出现少于两次时为空
+    pub average_interval: Option<f64>,
+    /// 最近一次出现的期号
+    pub last_seen_issue: Option<String>,
+    /// 最近一次出现的日期
+    pub last_seen_date: Option<String>,
+    /// 走势
+    pub trend: NumberTrend,
+    /// 冷热分类
+    pub category: NumberCategory,
+}
+
+/// 单期回测结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestDraw {
+    /// 被回测的期号
+    pub issue: String,
+    /// 投注注数
+    pub tickets: usize,
+    /// 单注最多命中的红球数
+    pub best_red_matches: usize,
+    /// 最好的中奖等级
+    pub best_tier: Option<u8>,
+    /// 本期成本（元）
+    pub cost: u64,
+    /// 本期奖金（元）
+    pub winnings: u64,
+    /// 本期投资回报率
+    pub roi: f64,
+    /// 累计成本（元）
+    pub cumulative_cost: u64,
+    /// 累计奖金（元）
+    pub cumulative_winnings: u64,
+    /// 累计投资回报率
+    pub cumulative_roi: f64,
+}
+
+/// 回测报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestReport {
+    pub draws: Vec<BacktestDraw>,
+    /// 总成本（元）
+    pub total_cost: u64,
+    /// 总奖金（元）
+    pub total_winnings: u64,
+    /// 总投资回报率
+    pub roi: f64,
+}
+
+/// 奇偶
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Parity {
+    Odd,
+    Even,
+}
+
+impl Parity {
+    pub fn of(value: u32) -> Self {
+        if value % 2 == 1 {
+            Parity::Odd
+        } else {
+            Parity::Even
+        }
+    }
+}
+
+/// 开奖记录查询条件，所有条件均可选，同时指定时取交集
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DrawFilter {
+    /// 必须全部开出的红球
+    pub required_reds: Option<Vec<u8>>,
+    /// 蓝球号码
+    pub blue: Option<u8>,
+    /// 蓝球奇偶
+    pub blue_parity: Option<Parity>,
+    /// 红球和值下限（含）
+    pub sum_min: Option<u32>,
+    /// 红球和值上限（含）
+    pub sum_max: Option<u32>,
+    /// 起始日期（含），格式 YYYY-MM-DD
+    pub date_from: Option<NaiveDate>,
+    /// 截止日期（含），格式 YYYY-MM-DD
+    pub date_to: Option<NaiveDate>,
+}
+
+/// 用户号码组合的得分排名
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CombinationRank {
+    /// 用户组合的得分
+    pub score: f64,
+    /// 百分位：得分高于多少比例（0-100）的抽样组合
+    pub percentile: f64,
+    /// 位于前百分之几（100 - percentile）
+    pub top_percent: f64,
+    /// 参与比较的抽样组合数
+    pub sample_size: usize,
+}
+
+/// 数据来源
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DataSource {
+    /// 本次从网络获取
+    Network,
+    /// 本地 CSV 缓存
+    Cache,
+    /// 网络不可用时生成的示例数据（随机号码，不是真实开奖）
+    Sample,
+}
+
+/// 数据加载结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadResult {
+    pub records: Vec<SsqRecord>,
+    /// 数据来源
+    pub source: DataSource,
+    /// 是否为示例数据
+    pub is_sample: bool,
+    /// 最后更新日期
+    pub last_update: Option<NaiveDate>,
+    /// 本次是否联网获取的原因说明
+    pub fetch_reason: String,
+    /// 加载过程中需要提示用户的警告
+    pub warnings: Vec<String>,
+}
+
+/// 蓝球推荐策略
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BlueStrategy {
+    /// 按历史出现频率
+    Frequency,
+    /// 按遗漏程度（当前遗漏 / 平均间隔）
+    Omission,
+    /// 按上一期蓝球之后各蓝球出现的转移概率
+    Markov,
+}
+
+/// 运行环境检查报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// 数据目录
+    pub data_dir: Option<String>,
+    /// 数据目录是否可写
+    pub data_dir_writable: bool,
+    /// 数据目录不可用的原因
+    pub data_dir_error: Option<String>,
+    /// 本地 CSV 是否存在
+    pub csv_exists: bool,
+    /// 本地记录数
+    pub local_record_count: usize,
+    /// 本地数据读取失败的原因
+    pub local_data_error: Option<String>,
+    /// 数据源是否可以连通
+    pub network_reachable: bool,
+    /// 连接失败的原因
+    pub network_error: Option<String>,
+}
+
+/// 时间衰减与平均统计的回测对比
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecencyComparison {
+    /// 所有历史同等对待
+    pub flat: BacktestReport,
+    /// 使用时间衰减
+    pub decayed: BacktestReport,
+    /// 时间衰减的总回报率是否更高
+    pub decay_helps: bool,
+}
+
+/// 单个号码的遗漏统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OmissionStat {
+    pub number: u8,
+    /// 当前遗漏
+    pub current: usize,
+    /// 最大遗漏
+    pub max: usize,
+    /// 平均间隔
+    pub average_interval: Option<f64>,
+}
+
+/// 分析快照包含的部分，默认全部包含
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapshotSections {
+    pub frequency: bool,
+    pub omission: bool,
+    pub odd_even: bool,
+    pub zone: bool,
+    pub consecutive: bool,
+    pub sum: bool,
+    pub span: bool,
+    pub tail: bool,
+}
+
+impl Default for SnapshotSections {
+    fn default() -> Self {
+        Self {
+            frequency: true,
+            omission: true,
+            odd_even: true,
+            zone: true,
+            consecutive: true,
+            sum: true,
+            span: true,
+            tail: true,
+        }
+    }
+}
+
+/// 全部统计的分析快照，未选择的部分为空
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisSnapshot {
+    /// 参与统计的期数
+    pub record_count: usize,
+    /// 最早期号
+    pub first_issue: Option<String>,
+    /// 最新期号
+    pub last_issue: Option<String>,
+    pub algorithm: AlgorithmType,
+    pub red_frequency: Option<Vec<BallFrequency>>,
+    pub blue_frequency: Option<Vec<BallFrequency>>,
+    pub red_omission: Option<Vec<OmissionStat>>,
+    pub blue_omission: Option<Vec<OmissionStat>>,
+    /// 奇数个数 -> 期数
+    pub odd_even: Option<Vec<(u8, usize)>>,
+    /// 三区比 -> 期数
+    pub zone: Option<Vec<(String, usize)>>,
+    /// 连号对数 -> 期数
+    pub consecutive: Option<Vec<(usize, usize)>>,
+    /// 和值分段起点 -> 期数
+    pub sum: Option<Vec<(u32, usize)>>,
+    /// 跨度 -> 期数
+    pub span: Option<Vec<(u8, usize)>>,
+    /// 红球尾数 -> 出现次数
+    pub tail: Option<Vec<(u8, usize)>>,
+}
+
+/// 两份数据集按期号比较的差异
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatasetDiff {
+    /// 新数据中新增的期号
+    pub added: Vec<String>,
+    /// 新数据中缺失的期号
+    pub removed: Vec<String>,
+    /// 期号相同但日期或号码不同的期号
+    pub changed: Vec<String>,
+}
+
+/// 预测结果及全部迭代中每个号码被抽中的次数
+///
+/// 统计的是每次迭代的全部抽样，包括之后因排除历史组合或去重而没有进入结果的候选，
+/// 反映的是加权抽样本身，而不是最终 10 注中的号码分布。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectionReport {
+    pub predictions: Vec<PredictionResult>,
+    /// 红球号码 -> 全部迭代中被抽中的次数
+    pub red_sampled: Vec<(u8, usize)>,
+    /// 蓝球号码 -> 全部迭代中被抽中的次数
+    pub blue_sampled: Vec<(u8, usize)>,
+}
+
+/// 相邻两期之间缺失的期号区间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueGap {
+    /// 缺口前的期号
+    pub after: String,
+    /// 缺口后的期号
+    pub before: String,
+    /// 缺失的期数
+    pub missing: u32,
+}
+
+/// 期号序列检查结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IssueSequenceReport {
+    pub gaps: Vec<IssueGap>,
+    /// 比前一期更早（顺序错乱）的期号
+    pub out_of_order: Vec<String>,
+    /// 无法解析的期号
+    pub unparsable: Vec<String>,
+}
+
+/// 红球大小号的多数方：大号 17-33，小号 1-16，各 3 个时为平衡
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SizeSide {
+    Big,
+    Small,
+    Balanced,
+}
+
+/// 某项属性连续保持不变的期数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Streak<T> {
+    /// 最新一期的取值，数据不足时为 `None`
+    pub value: Option<T>,
+    /// 当前连续期数
+    pub current: usize,
+    /// 历史最长连续期数（不区分取值）
+    pub max: usize,
+}
+
+/// 连续走势：红球和值奇偶、大小号多数方、蓝球奇偶
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreakReport {
+    pub sum_parity: Streak<Parity>,
+    pub size_side: Streak<SizeSide>,
+    pub blue_parity: Streak<Parity>,
+}
+
+/// 一注红球平均能命中的个数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpectedMatches {
+    /// 理论期望，任何号码都是 6 × 6/33 ≈ 1.09
+    pub theoretical: f64,
+    /// 按历史出现率计算的期望
+    pub empirical: f64,
+}
+
+/// "热号六码"策略的回测：每期都买前 `window` 期最热的 6 个红球和最热的蓝球
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotSixReport {
+    /// 每期的 (期号, 中奖等级)，未中奖为 `None`
+    pub draws: Vec<(String, Option<u8>)>,
+    /// 中奖（任意等级）的期数
+    pub hits: usize,
+    /// 中奖期数 / 回测期数
+    pub hit_rate: f64,
+}
+
+/// 某个算法的权重分布特征
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlgorithmProfile {
+    pub algorithm: AlgorithmType,
+    /// 红球权重分布的香农熵（比特）
+    pub red_entropy: f64,
+    /// 蓝球权重分布的香农熵（比特）
+    pub blue_entropy: f64,
+    /// 红球均匀分布的熵 log2(33)，作为上限参考
+    pub max_red_entropy: f64,
+    /// 蓝球均匀分布的熵 log2(16)，作为上限参考
+    pub max_blue_entropy: f64,
+}
+
+/// 多注号码对照一期开奖的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TicketCheck {
+    /// 对照的期号
+    pub issue: String,
+    /// 每注的中奖等级，顺序与输入一致，未中奖为 `None`
+    pub tiers: Vec<Option<u8>>,
+    /// 总奖金（元），一、二等奖按估计值计算
+    pub total_winnings: u64,
+}
+
+/// 一注预测对照实际开奖的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PredictionGrade {
+    pub prediction: PredictionResult,
+    /// 命中的红球个数
+    pub red_matches: usize,
+    /// 蓝球是否命中
+    pub blue_match: bool,
+    /// 中奖等级，未中奖为 `None`
+    pub tier: Option<u8>,
+}
+
+/// 一组预测的对奖结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradeReport {
+    /// 对照的期号
+    pub issue: String,
+    /// 每注结果，顺序与输入一致
+    pub grades: Vec<PredictionGrade>,
+    /// 结果最好的一注在 `grades` 中的下标（先比中奖等级，再比命中红球数）
+    pub best: Option<usize>,
+}
+
+/// 红球实际出现次数与均匀分布期望的偏差
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FairnessReport {
+    /// 每个红球的 (号码, 实际次数 - 期望次数)
+    pub deviations: Vec<(u8, f64)>,
+    /// 每个红球的期望出现次数：总期数 × 6/33
+    pub expected: f64,
+    /// 卡方统计量 Σ(实际 - 期望)² / 期望
+    pub chi_square: f64,
+    /// 自由度（号码个数 - 1）
+    pub degrees_of_freedom: usize,
+    /// 显著性水平 0.05 下的卡方临界值，统计量超过它才说明分布显著偏离均匀
+    pub critical_value: f64,
+}
+
+/// 合并两个 CSV 文件的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeReport {
+    /// 合并后的总记录数
+    pub total: usize,
+    /// 只在第二个文件中出现、被加入的期号
+    pub added: Vec<String>,
+    /// 两个文件中完全相同的期号
+    pub duplicates: Vec<String>,
+    /// 两个文件中内容不一致的期号（保留第一个文件的记录）
+    pub conflicts: Vec<String>,
+}
+
+/// 合并时同一期号已存在于本地的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum MergePolicy {
+    /// 保留本地记录（默认，避免无谓的改动）
+    #[default]
+    KeepExisting,
+    /// 新获取的记录与本地不一致时覆盖本地记录，用于数据源修正了之前的错误
+    PreferFetched,
+}
+
+/// 自助法（bootstrap）估计的号码权重区间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightInterval {
+    pub number: u8,
+    /// 各次重采样权重的平均值
+    pub mean: f64,
+    /// 第 5 百分位
+    pub p5: f64,
+    /// 第 95 百分位
+    pub p95: f64,
+}
+
+/// 某个号码历史上最长的连续未出现区间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbsenceRun {
+    pub number: u8,
+    /// 连续未出现的期数，每期都出现时为 0
+    pub length: usize,
+    /// 区间第一期的期号
+    pub first_issue: Option<String>,
+    /// 区间最后一期的期号
+    pub last_issue: Option<String>,
+}
+
+/// 批量分析中的单项请求，`analysis` 字段指定分析类型，其余字段为该分析的参数
+///
+/// `algorithm` 与其他命令一样使用 "hot"、"cold"、"realistic"。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "analysis", rename_all = "snake_case")]
+pub enum AnalysisRequest {
+    Frequency { algorithm: String, is_blue: bool },
+    Omission { is_blue: bool },
+    OddEven,
+    Zone,
+    Decade,
+    Consecutive,
+    ConsecutiveRuns,
+    Sum,
+    Span,
+    Tail,
+    Streaks,
+    Fairness,
+    OmissionPressure { is_blue: bool },
+    LongestAbsence { number: u8, is_blue: bool },
+}
+
+/// 批量分析中的单项结果，与请求一一对应；单项出错时为 `Error`，不影响其他项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "analysis", content = "result", rename_all = "snake_case")]
+pub enum AnalysisResponse {
+    Frequency(Vec<BallFrequency>),
+    Omission(Vec<OmissionStat>),
+    OddEven(Vec<(u8, usize)>),
+    Zone(Vec<(String, usize)>),
+    Decade(Vec<(String, usize)>),
+    Consecutive(Vec<(usize, usize)>),
+    ConsecutiveRuns(Vec<(u8, usize)>),
+    Sum(Vec<(u32, usize)>),
+    Span(Vec<(u8, usize)>),
+    Tail(Vec<(u8, usize)>),
+    Streaks(StreakReport),
+    Fairness(FairnessReport),
+    OmissionPressure(Vec<(u8, f64)>),
+    LongestAbsence(AbsenceRun),
+    Error(String),
+}
+
+/// 模拟连续多期投注同一组号码的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaySimulation {
+    /// 每次模拟的期数
+    pub draws: usize,
+    /// 每期投注的注数
+    pub tickets_per_draw: usize,
+    /// 模拟重复的次数
+    pub trials: usize,
+    /// 全部期数的总成本（元）
+    pub total_cost: u64,
+    /// 总奖金的平均值（元）
+    pub expected_winnings: f64,
+    /// 净收益（奖金 - 成本）的平均值（元）
+    pub expected_net: f64,
+    /// 净收益的方差
+    pub net_variance: f64,
+    /// 净收益为正的模拟次数占比
+    pub profit_probability: f64,
+}
+
+/// 某个开奖星期几的红球频率
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeekdayFrequency {
+    /// 开奖日，日期无法解析的记录为空
+    pub weekday: Option<Weekday>,
+    /// 该组的期数
+    pub draws: usize,
+    pub red_frequency: Vec<BallFrequency>,
+}
+
+/// 数据集指纹，用于确认两份数据是否完全相同
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetFingerprint {
+    /// 按期号排序后的期号和号码的 SHA-256（十六进制）
+    pub hash: String,
+    pub record_count: usize,
+    /// 最早一期的开奖日期
+    pub first_date: Option<String>,
+    /// 最新一期的开奖日期
+    pub last_date: Option<String>,
+}
+
+/// 异常记录的类型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AnomalyKind {
+    /// 号码超出范围
+    OutOfRange,
+    /// 同一期红球重复
+    DuplicateRed,
+    /// 日期无法解析或晚于今天
+    InvalidDate,
+    /// 和值远超历史正常范围
+    ExtremeSum,
+}
+
+/// 一条异常记录，同一期有多种异常时分别报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyReport {
+    pub issue: String,
+    pub kind: AnomalyKind,
+    /// 具体说明
+    pub detail: String,
+}
+
+/// 一组投注号码的覆盖情况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageReport {
+    /// 覆盖的不同红球个数（共 33 个）
+    pub distinct_reds: usize,
+    /// 覆盖的不同蓝球个数（共 16 个）
+    pub distinct_blues: usize,
+    /// 没有任何一注包含的红球
+    pub uncovered_reds: Vec<u8>,
+    /// 任意两注之间相同红球个数的最大值，不足两注时为 0
+    pub max_overlap: usize,
+    /// 重叠最多的两注在输入中的下标
+    pub max_overlap_pair: Option<(usize, usize)>,
+}
+
+/// 号码温度各分项的权重，按权重加权平均，权重之和不必为 1
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemperatureWeights {
+    /// 时间衰减后的出现率
+    pub frequency: f64,
+    /// 当前遗漏（越短越热）
+    pub omission: f64,
+    /// 近期走势
+    pub trend: f64,
+}
+
+impl Default for TemperatureWeights {
+    fn default() -> Self {
+        Self {
+            frequency: 0.5,
+            omission: 0.3,
+            trend: 0.2,
+        }
+    }
+}
+
+/// 蓝球与红球和值之间关系的统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationshipModel {
+    /// 参与统计的期数
+    pub samples: usize,
+    /// 红球和值与蓝球的皮尔逊相关系数，期数不足或无变化时为空
+    pub correlation: Option<f64>,
+    /// 蓝球 = 和值 % 16 + 1 的期数
+    pub mod_rule_hits: usize,
+    /// 上述规则的实际命中率
+    pub mod_rule_hit_rate: f64,
+    /// 完全随机时的命中率（1/16）
+    pub expected_hit_rate: f64,
+    /// 对关系强弱的文字说明
+    pub assessment: String,
+}
+
+/// 重新解析已保存的原始响应的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReparseReport {
+    /// 解析的文件名
+    pub file: String,
+    pub records: Vec<SsqRecord>,
+    /// 网页响应的解析诊断，JSON 响应为空
+    pub diagnostics: Option<ParseDiagnostics>,
+}
+
+/// 号码"该出"的预测
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DueForecast {
+    pub number: u8,
+    /// 平均间隔，出现少于两次时为空
+    pub average_interval: Option<f64>,
+    /// 间隔的标准差
+    pub interval_std: Option<f64>,
+    /// 当前遗漏
+    pub current_omission: usize,
+    /// 距离按平均间隔"应该"再出现还有几期（平均间隔 - 当前遗漏，不小于 0）
+    pub draws_until_due: Option<f64>,
+    /// 可信度（0-1），间隔越稳定越高，样本太少时为 0
+    pub confidence: f64,
+}
+
+/// 假如历史上每期都按预测投注的资金变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BankrollReport {
+    /// 投注的期数
+    pub draws: usize,
+    pub tickets_per_draw: usize,
+    /// 总成本（元）
+    pub total_cost: u64,
+    /// 总奖金（元）
+    pub total_winnings: u64,
+    /// 最终盈亏（元），从 0 开始
+    pub final_balance: i64,
+    /// 最大回撤：盈亏从此前最高点回落的最大金额（元）
+    pub max_drawdown: i64,
+    /// 单期盈亏最好的一期（期号, 盈亏）
+    pub best_draw: Option<(String, i64)>,
+    /// 单期盈亏最差的一期（期号, 盈亏）
+    pub worst_draw: Option<(String, i64)>,
+    /// 每期结束后的累计盈亏（期号, 盈亏）
+    pub balance_history: Vec<(String, i64)>,
+    /// 生成预测使用的熵字符串，传回同一字符串可以重现本次结果
+    pub entropy: String,
+}
+
+/// 可验证的预测结果：用户事先公布熵字符串（或其哈希），开奖后任何人都可以用同一字符串
+/// 重新生成并核对这些号码。这只说明号码不是事后挑选的，并不能提高中奖概率
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FairPredictions {
+    /// 实际使用的种子（熵字符串的 SHA-256，十六进制）
+    pub seed: String,
+    pub predictions: Vec<PredictionResult>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(reds: Vec<u8>, blue: u8) -> SsqRecord {
+        SsqRecord::new("24001".to_string(), "2024-01-02".to_string(), reds, blue)
+    }
+
+    #[test]
+    fn same_draw_ignores_red_order_and_note() {
+        let sorted = record(vec![1, 5, 12, 18, 25, 33], 7);
+        let mut as_drawn = record(vec![18, 5, 33, 1, 25, 12], 7);
+        as_drawn.note = Some("出球顺序".to_string());
+        assert!(sorted.same_draw(&as_drawn));
+
+        assert!(!sorted.same_draw(&record(vec![1, 5, 12, 18, 25, 32], 7)));
+        assert!(!sorted.same_draw(&record(vec![1, 5, 12, 18, 25, 33], 8)));
+    }
+
+    #[test]
+    fn note_column_is_optional_in_csv() {
+        let legacy = "issue,date,red1,red2,red3,red4,red5,red6,blue_ball\n\
+                      24001,2024-01-02,1,5,12,18,25,33,7\n";
+        let with_note = "issue,date,red1,red2,red3,red4,red5,red6,blue_ball,note\n\
+                         24001,2024-01-02,1,5,12,18,25,33,7,数据存疑\n\
+                         24002,2024-01-04,2,6,13,19,26,31,8,\n";
+        let parse = |content: &str| -> Vec<SsqRecord> {
+            csv::Reader::from_reader(content.as_bytes())
+                .deserialize()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+
+        assert_eq!(parse(legacy)[0].note, None);
+        let records = parse(with_note);
+        assert_eq!(records[0].note.as_deref(), Some("数据存疑"));
+        assert_eq!(records[1].note, None);
+    }
+}
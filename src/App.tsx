@@ -1,11 +1,13 @@
 import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import * as echarts from 'echarts';
-import type { SsqRecord, BallFrequency, PredictionResult, AlgorithmType } from "./types";
+import type { SsqRecord, BallFrequency, PredictionResult, AlgorithmType, LoadResult } from "./types";
 
 function App() {
   const [loading, setLoading] = useState(false);
   const [records, setRecords] = useState<SsqRecord[]>([]);
+  const [isSample, setIsSample] = useState(false);
+  const [warnings, setWarnings] = useState<string[]>([]);
   const [algorithm, setAlgorithm] = useState<AlgorithmType>("hot");
   const [redFrequencies, setRedFrequencies] = useState<BallFrequency[]>([]);
   const [blueFrequencies, setBlueFrequencies] = useState<BallFrequency[]>([]);
@@ -22,9 +24,11 @@ function App() {
     setLoading(true);
     setError("");
     try {
-      const data = await invoke<SsqRecord[]>("load_and_update_data");
-      setRecords(data);
-      await analyzeFrequency(data, algorithm);
+      const result = await invoke<LoadResult>("load_and_update_data");
+      setRecords(result.records);
+      setIsSample(result.is_sample);
+      setWarnings(result.warnings);
+      await analyzeFrequency(algorithm);
     } catch (err) {
       setError(`加载失败: ${err}`);
       console.error(err);
@@ -33,11 +37,11 @@ function App() {
     }
   };
 
-  const analyzeFrequency = async (data: SsqRecord[], algo: AlgorithmType) => {
+  const analyzeFrequency = async (algo: AlgorithmType) => {
     try {
       const [redFreq, blueFreq] = await invoke<[BallFrequency[], BallFrequency[]]>(
         "analyze_frequency",
-        { records: data, algorithm: algo }
+        { algorithm: algo }
       );
       setRedFrequencies(redFreq);
       setBlueFrequencies(blueFreq);
@@ -56,7 +60,6 @@ function App() {
     setError("");
     try {
       const result = await invoke<PredictionResult[]>("generate_predictions", {
-        records,
         algorithm,
       });
       setPredictions(result);
@@ -73,7 +76,7 @@ function App() {
     setAlgorithm(algo);
     setPredictions([]);
     if (records.length > 0) {
-      await analyzeFrequency(records, algo);
+      await analyzeFrequency(algo);
     }
   };
 
@@ -248,6 +251,16 @@ function App() {
     <main className="min-h-screen p-5 max-w-[1400px] mx-auto">
       <h1 className="text-center text-white text-4xl mb-8 drop-shadow-[2px_2px_4px_rgba(0,0,0,0.3)]">🎱 双色球预测工具</h1>
       {error && <div className="bg-red-500 text-white p-4 rounded-lg mb-5 text-center">{error}</div>}
+      {isSample && (
+        <div className="bg-yellow-500 text-white p-4 rounded-lg mb-5 text-center">
+          当前为随机生成的示例数据，并非真实开奖记录，预测结果仅供演示
+        </div>
+      )}
+      {warnings.map((warning) => (
+        <div key={warning} className="bg-yellow-500 text-white p-4 rounded-lg mb-5 text-center">
+          {warning}
+        </div>
+      ))}
       <div className="flex gap-5 items-center justify-center mb-8 flex-wrap">
         <button 
           onClick={loadData} 
@@ -267,6 +280,7 @@ function App() {
           >
             <option value="hot">热号恒热</option>
             <option value="cold">冷号反弹</option>
+            <option value="realistic">贴近真实</option>
           </select>
         </div>
         <button
@@ -3,6 +3,8 @@ export interface SsqRecord {
   date: string;
   red_balls: number[];
   blue_ball: number;
+  note: string | null;
+  synthetic: boolean;
 }
 
 export interface BallFrequency {
@@ -17,4 +19,15 @@ export interface PredictionResult {
   score: number;
 }
 
-export type AlgorithmType = 'hot' | 'cold';
+export type AlgorithmType = 'hot' | 'cold' | 'realistic';
+
+export type DataSource = 'Network' | 'Cache' | 'Sample';
+
+export interface LoadResult {
+  records: SsqRecord[];
+  source: DataSource;
+  is_sample: boolean;
+  last_update: string | null;
+  fetch_reason: string;
+  warnings: string[];
+}